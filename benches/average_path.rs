@@ -1,6 +1,6 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use rand::prelude::*;
-use seastar::{astar, Grid, Point};
+use seastar::{astar, generators, Grid, Point};
 
 const SEEDS: [u64; 3] = [
     2210748027404127321,
@@ -10,7 +10,6 @@ const SEEDS: [u64; 3] = [
 
 fn create_test_grid(w: usize, h: usize, seed: u64) -> (Grid, Point, Point) {
     let mut rng = StdRng::seed_from_u64(seed);
-    let mut grid = Grid::new(w, h);
 
     let start = Point {
         x: rng.gen_range(0..w as isize),
@@ -22,18 +21,9 @@ fn create_test_grid(w: usize, h: usize, seed: u64) -> (Grid, Point, Point) {
         y: rng.gen_range(0..h as isize),
     };
 
-    let is_endpoint = |x, y| {
-        (x == start.x as usize && y == start.y as usize)
-            || (x == end.x as usize && y == end.y as usize)
-    };
-
-    for y in 0..h {
-        for x in 0..w {
-            if rng.gen_bool(0.2) && !is_endpoint(x, y) {
-                *grid.get_mut(x as isize, y as isize).unwrap() = true;
-            }
-        }
-    }
+    let mut grid = generators::noise(w, h, 0.2, seed);
+    grid.set(start.x, start.y, false);
+    grid.set(end.x, end.y, false);
 
     (grid, start, end)
 }
@@ -1,9 +1,8 @@
 use iridescent::{Styled, GREEN, RED};
 use rand::{thread_rng, Rng};
-use seastar::{astar, Grid, Point};
+use seastar::{astar, generators, Grid, Point};
 
 fn setup(w: usize, h: usize) -> (Grid, Point, Point) {
-    let mut grid = Grid::new(w, h);
     let mut rng = thread_rng();
 
     let start = Point {
@@ -16,19 +15,9 @@ fn setup(w: usize, h: usize) -> (Grid, Point, Point) {
         y: rng.gen_range(0..h as isize),
     };
 
-    for y in 0..h {
-        for x in 0..w {
-            if rng.gen_bool(0.2) {
-                if x == start.x as usize && y == start.y as usize
-                    || x == end.x as usize && y == end.y as usize
-                {
-                    continue; // Leave as None
-                } else {
-                    *grid.get_mut(x as isize, y as isize).unwrap() = true;
-                }
-            }
-        }
-    }
+    let mut grid = generators::noise(w, h, 0.2, rng.gen());
+    grid.set(start.x, start.y, false);
+    grid.set(end.x, end.y, false);
 
     (grid, start, end)
 }
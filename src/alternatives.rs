@@ -0,0 +1,262 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+};
+
+use crate::{get_neighbor_points, manhattan_distance, point_to_index, Grid, Point};
+
+/// Finds up to `k` shortest loopless paths from `start` to `end` using Yen's
+/// algorithm, in order of increasing length. The first path is the one
+/// `astar` would find; fewer than `k` paths are returned if no more exist.
+///
+/// Paths may share most of their points. See `alternative_paths` for routes
+/// that avoid each other.
+#[must_use]
+pub fn k_shortest_paths(grid: &Grid, start: Point, end: Point, k: usize) -> Vec<Vec<Point>> {
+    let capacity = grid.width() * grid.height();
+    let mut blocked_nodes = vec![false; capacity];
+    let mut blocked_edges = HashSet::new();
+
+    let mut paths: Vec<Vec<Point>> = Vec::with_capacity(k);
+    let mut candidates: Vec<Vec<Point>> = Vec::new();
+
+    if k == 0 {
+        return paths;
+    }
+
+    match search(grid, start, end, &blocked_nodes, &blocked_edges, |_| 1) {
+        Some(path) => paths.push(path),
+        None => return paths,
+    }
+
+    while paths.len() < k {
+        let previous = &paths[paths.len() - 1];
+
+        // Deviate from the previous path at each of its points in turn, never
+        // reusing the root up to that point nor the edges already taken from
+        // it by an earlier path.
+        for i in 0..previous.len() - 1 {
+            let spur = previous[i];
+            let root = &previous[..=i];
+
+            blocked_edges.clear();
+            for path in &paths {
+                if path.len() > i + 1 && &path[..=i] == root {
+                    blocked_edges.insert((path[i], path[i + 1]));
+                }
+            }
+
+            for point in &root[..i] {
+                blocked_nodes[point_to_index(*point, grid.width())] = true;
+            }
+
+            let spur_path = search(grid, spur, end, &blocked_nodes, &blocked_edges, |_| 1);
+
+            for point in &root[..i] {
+                blocked_nodes[point_to_index(*point, grid.width())] = false;
+            }
+
+            if let Some(spur_path) = spur_path {
+                let mut candidate = root[..i].to_vec();
+                candidate.extend(spur_path);
+
+                if !candidates.contains(&candidate) && !paths.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+
+        let Some(best) = candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, path)| path.len())
+            .map(|(i, _)| i)
+        else {
+            break;
+        };
+
+        paths.push(candidates.remove(best));
+    }
+
+    paths
+}
+
+/// Finds up to `count` distinct paths from `start` to `end` that avoid each
+/// other where possible. After each path is found, every point along it costs
+/// an extra `penalty` to walk through for the following searches, so later
+/// paths only overlap earlier ones where a detour would cost more.
+///
+/// The first path is always a shortest path. Fewer than `count` paths are
+/// returned if the penalty is not enough to produce a new distinct path.
+#[must_use]
+pub fn alternative_paths(
+    grid: &Grid,
+    start: Point,
+    end: Point,
+    count: usize,
+    penalty: usize,
+) -> Vec<Vec<Point>> {
+    let capacity = grid.width() * grid.height();
+    let blocked_nodes = vec![false; capacity];
+    let blocked_edges = HashSet::new();
+    let mut uses = vec![0; capacity];
+
+    let mut paths: Vec<Vec<Point>> = Vec::with_capacity(count);
+
+    while paths.len() < count {
+        let cost = |point: Point| 1 + penalty * uses[point_to_index(point, grid.width())];
+
+        let Some(path) = search(grid, start, end, &blocked_nodes, &blocked_edges, cost) else {
+            break;
+        };
+
+        if paths.contains(&path) {
+            break;
+        }
+
+        for point in &path {
+            uses[point_to_index(*point, grid.width())] += 1;
+        }
+
+        paths.push(path);
+    }
+
+    paths
+}
+
+/// A* with a cost for entering each point, which must be at least one, and
+/// optional nodes and directed edges to avoid.
+fn search(
+    grid: &Grid,
+    start: Point,
+    end: Point,
+    blocked_nodes: &[bool],
+    blocked_edges: &HashSet<(Point, Point)>,
+    cost: impl Fn(Point) -> usize,
+) -> Option<Vec<Point>> {
+    let width = grid.width();
+    let capacity = width * grid.height();
+
+    grid.index(start.x, start.y)?;
+    grid.index(end.x, end.y)?;
+
+    let mut open_nodes = BinaryHeap::new();
+    let mut closed_nodes = vec![false; capacity];
+    let mut g_scores = vec![usize::MAX; capacity];
+    let mut parents = vec![usize::MAX; capacity];
+
+    let start_index = point_to_index(start, width);
+    g_scores[start_index] = 0;
+    parents[start_index] = start_index;
+    open_nodes.push((
+        Reverse(manhattan_distance(&start, &end) as usize),
+        start_index,
+    ));
+
+    while let Some((_, index)) = open_nodes.pop() {
+        if closed_nodes[index] {
+            continue;
+        }
+        closed_nodes[index] = true;
+
+        let point = Point::new((index % width) as isize, (index / width) as isize);
+
+        if point == end {
+            let mut path = vec![point];
+            let mut current = index;
+            while parents[current] != current {
+                current = parents[current];
+                path.push(Point::new(
+                    (current % width) as isize,
+                    (current / width) as isize,
+                ));
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for neighbor in get_neighbor_points(grid, point) {
+            let neighbor_index = point_to_index(neighbor, width);
+            if closed_nodes[neighbor_index]
+                || blocked_nodes[neighbor_index]
+                || blocked_edges.contains(&(point, neighbor))
+            {
+                continue;
+            }
+
+            let tentative_g = g_scores[index] + cost(neighbor);
+            if tentative_g >= g_scores[neighbor_index] {
+                continue;
+            }
+
+            g_scores[neighbor_index] = tentative_g;
+            parents[neighbor_index] = index;
+
+            let f = tentative_g + manhattan_distance(&neighbor, &end) as usize;
+            open_nodes.push((Reverse(f), neighbor_index));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_k_shortest_paths() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false],
+            vec![false, true,  false],
+            vec![false, false, false],
+        ]);
+
+        let start = Point::new(0, 0);
+        let end = Point::new(2, 2);
+
+        let paths = k_shortest_paths(&grid, start, end, 5);
+
+        assert_eq!(paths.len(), 2);
+        assert_ne!(paths[0], paths[1]);
+        for path in &paths {
+            assert_eq!(path.len(), 5);
+            assert_eq!(path.first(), Some(&start));
+            assert_eq!(path.last(), Some(&end));
+        }
+    }
+
+    #[test]
+    fn test_k_shortest_paths_increasing() {
+        let grid = Grid::new(4, 3);
+        let paths = k_shortest_paths(&grid, Point::new(0, 0), Point::new(3, 0), 20);
+
+        assert_eq!(paths.len(), 20);
+        assert!(paths.windows(2).all(|w| w[0].len() <= w[1].len()));
+        for (i, path) in paths.iter().enumerate() {
+            assert!(!paths[..i].contains(path), "Duplicate path {path:?}");
+
+            let unique: HashSet<&Point> = path.iter().collect();
+            assert_eq!(unique.len(), path.len(), "Path {path:?} has a loop");
+        }
+    }
+
+    #[test]
+    fn test_alternative_paths() {
+        let grid = Grid::new(5, 3);
+        let start = Point::new(0, 1);
+        let end = Point::new(4, 1);
+
+        let paths = alternative_paths(&grid, start, end, 3, 10);
+
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[0].len(), 5);
+
+        let shared = paths[1][1..paths[1].len() - 1]
+            .iter()
+            .filter(|point| paths[0].contains(point))
+            .count();
+        assert_eq!(shared, 0);
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::BinaryHeap;
+
+use crate::{get_neighbor_points, manhattan_distance, node::OpenNode, Grid, Point};
+
+/// A path found by `AraStar`, along with how far from optimal it may be.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnytimePath {
+    /// The points of the path, from start to end.
+    pub path: Vec<Point>,
+    /// The path is at most this many times longer than the shortest path. A
+    /// bound of `1.0` means the path is optimal.
+    pub bound: f64,
+}
+
+/// An anytime planner using the Anytime Repairing A* (ARA*) algorithm.
+///
+/// The planner is an iterator: the first item is found quickly using a
+/// heuristic inflated by the initial epsilon, and each following item is an
+/// improved path found by reusing the previous search with a smaller epsilon.
+/// Iteration ends once an optimal path has been returned, or immediately if
+/// there is no path. Stop iterating at any point, such as when a frame
+/// deadline is reached, and use the last path returned.
+#[derive(Debug, Clone)]
+pub struct AraStar<'a> {
+    grid: &'a Grid,
+    end: Point,
+    epsilon: f64,
+    decrement: f64,
+    started: bool,
+    finished: bool,
+    g_scores: Vec<usize>,
+    parents: Vec<usize>,
+    closed_nodes: Vec<bool>,
+    in_open: Vec<bool>,
+    in_inconsistent: Vec<bool>,
+    open_nodes: BinaryHeap<OpenNode>,
+    /// Nodes whose cost improved after they were expanded in the current
+    /// iteration, which are reopened in the next one.
+    inconsistent: Vec<usize>,
+}
+
+impl<'a> AraStar<'a> {
+    /// Creates a planner from `start` to `end`. The first path is bounded by
+    /// `epsilon`, which is then lowered by `decrement` for every following
+    /// path until it reaches `1.0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `epsilon` is less than `1.0`, or if `decrement` is not
+    /// positive.
+    #[must_use]
+    pub fn new(grid: &'a Grid, start: Point, end: Point, epsilon: f64, decrement: f64) -> Self {
+        assert!(epsilon >= 1.0, "epsilon must be at least 1.0");
+        assert!(decrement > 0.0, "decrement must be positive");
+
+        let capacity = grid.width() * grid.height();
+        let mut planner = Self {
+            grid,
+            end,
+            epsilon,
+            decrement,
+            started: false,
+            finished: false,
+            g_scores: vec![usize::MAX; capacity],
+            parents: vec![usize::MAX; capacity],
+            closed_nodes: vec![false; capacity],
+            in_open: vec![false; capacity],
+            in_inconsistent: vec![false; capacity],
+            open_nodes: BinaryHeap::new(),
+            inconsistent: Vec::new(),
+        };
+
+        match (grid.index(start.x, start.y), grid.index(end.x, end.y)) {
+            (Some(start_index), Some(_)) => {
+                planner.g_scores[start_index] = 0;
+                planner.parents[start_index] = start_index;
+                planner.push(start_index);
+            }
+            _ => planner.finished = true,
+        }
+
+        planner
+    }
+
+    /// Returns the inflation factor used by the most recent search.
+    #[must_use]
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    /// Expands nodes until no node in the open set could lead to a path
+    /// better than the current one by more than a factor of epsilon.
+    fn improve_path(&mut self, end_index: usize) {
+        while let Some(node) = self.open_nodes.peek().copied() {
+            // Entries left behind when a node's cost improved are skipped.
+            if !self.in_open[node.index] {
+                self.open_nodes.pop();
+                continue;
+            }
+
+            if self.g_scores[end_index] as f64 <= node.f {
+                break;
+            }
+
+            self.open_nodes.pop();
+            self.in_open[node.index] = false;
+            self.closed_nodes[node.index] = true;
+
+            let point = self.point(node.index);
+            let tentative_g = self.g_scores[node.index] + 1;
+
+            for neighbor in get_neighbor_points(self.grid, point) {
+                let Some(neighbor_index) = self.grid.index(neighbor.x, neighbor.y) else {
+                    continue;
+                };
+
+                if tentative_g >= self.g_scores[neighbor_index] {
+                    continue;
+                }
+
+                self.g_scores[neighbor_index] = tentative_g;
+                self.parents[neighbor_index] = node.index;
+
+                if !self.closed_nodes[neighbor_index] {
+                    self.push(neighbor_index);
+                } else if !self.in_inconsistent[neighbor_index] {
+                    self.in_inconsistent[neighbor_index] = true;
+                    self.inconsistent.push(neighbor_index);
+                }
+            }
+        }
+    }
+
+    /// Moves inconsistent nodes back into the open set and reorders it for
+    /// the current epsilon.
+    fn reopen(&mut self) {
+        for index in std::mem::take(&mut self.inconsistent) {
+            self.in_inconsistent[index] = false;
+            self.in_open[index] = true;
+        }
+
+        self.open_nodes.clear();
+        for index in 0..self.in_open.len() {
+            if self.in_open[index] {
+                self.push(index);
+            }
+        }
+
+        self.closed_nodes.fill(false);
+    }
+
+    /// Returns the suboptimality bound of the current path, which can be
+    /// tighter than epsilon.
+    fn bound(&self, end_index: usize) -> f64 {
+        let lower_bound = self
+            .in_open
+            .iter()
+            .zip(&self.in_inconsistent)
+            .enumerate()
+            .filter(|(_, (&open, &inconsistent))| open || inconsistent)
+            .map(|(index, _)| self.g_scores[index] + self.h(index))
+            .min();
+
+        match lower_bound {
+            Some(lower_bound) if lower_bound > 0 => {
+                let ratio = self.g_scores[end_index] as f64 / lower_bound as f64;
+                self.epsilon.min(ratio).max(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+
+    fn push(&mut self, index: usize) {
+        let g = self.g_scores[index];
+        self.in_open[index] = true;
+        self.open_nodes.push(OpenNode {
+            f: g as f64 + self.epsilon * self.h(index) as f64,
+            index,
+        });
+    }
+
+    fn h(&self, index: usize) -> usize {
+        manhattan_distance(&self.point(index), &self.end) as usize
+    }
+
+    fn point(&self, index: usize) -> Point {
+        let width = self.grid.width();
+        Point::new((index % width) as isize, (index / width) as isize)
+    }
+}
+
+impl Iterator for AraStar<'_> {
+    type Item = AnytimePath;
+
+    fn next(&mut self) -> Option<AnytimePath> {
+        if self.finished {
+            return None;
+        }
+
+        if self.started {
+            self.epsilon = (self.epsilon - self.decrement).max(1.0);
+            self.reopen();
+        }
+        self.started = true;
+
+        let end_index = self.grid.index(self.end.x, self.end.y)?;
+        self.improve_path(end_index);
+
+        if self.g_scores[end_index] == usize::MAX {
+            self.finished = true;
+            return None;
+        }
+
+        let bound = self.bound(end_index);
+        if bound <= 1.0 {
+            self.finished = true;
+        }
+
+        let mut path = vec![self.end];
+        let mut index = end_index;
+        while self.parents[index] != index {
+            index = self.parents[index];
+            path.push(self.point(index));
+        }
+        path.reverse();
+
+        Some(AnytimePath { path, bound })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar;
+
+    #[test]
+    fn test_paths_improve() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false, false, false],
+            vec![false, true,  true,  true,  true,  false],
+            vec![false, false, false, false, true,  false],
+            vec![true,  true,  true,  false, true,  false],
+            vec![false, false, false, false, false, false],
+        ]);
+
+        let start = Point::new(0, 2);
+        let end = Point::new(5, 4);
+        let optimal = astar(&grid, start, end).unwrap().len();
+
+        let paths: Vec<AnytimePath> = AraStar::new(&grid, start, end, 3.0, 0.5).collect();
+
+        assert!(!paths.is_empty());
+        for result in &paths {
+            assert_eq!(result.path.first(), Some(&start));
+            assert_eq!(result.path.last(), Some(&end));
+            assert!((result.path.len() - 1) as f64 <= result.bound * (optimal - 1) as f64);
+        }
+
+        let last = paths.last().unwrap();
+        assert_eq!(last.bound, 1.0);
+        assert_eq!(last.path.len(), optimal);
+    }
+
+    #[test]
+    fn test_no_path() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, true,  false],
+        ]);
+
+        let mut planner = AraStar::new(&grid, Point::new(0, 0), Point::new(2, 0), 2.0, 0.5);
+
+        assert!(planner.next().is_none());
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use crate::{astar, manhattan_distance, Grid, Point};
+
+/// A `Grid` that memoizes the results of `astar` for repeated start and end
+/// pairs.
+///
+/// Changes to the grid go through `PathCache::set`, which only discards the
+/// cached results the change could affect: paths through a node that becomes
+/// blocked, and, when a node is opened, paths that could become shorter by
+/// passing through it, as well as cached failures.
+#[derive(Debug, Clone)]
+pub struct PathCache {
+    grid: Grid,
+    paths: HashMap<(Point, Point), Option<Vec<Point>>>,
+}
+
+impl PathCache {
+    #[must_use]
+    pub fn new(grid: Grid) -> Self {
+        Self {
+            grid,
+            paths: HashMap::new(),
+        }
+    }
+
+    /// Returns the underlying grid.
+    #[must_use]
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Consumes the cache, returning the underlying grid.
+    #[must_use]
+    pub fn into_grid(self) -> Grid {
+        self.grid
+    }
+
+    /// Returns the number of cached results, including failures.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Returns whether there are no cached results.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Discards every cached result.
+    pub fn clear(&mut self) {
+        self.paths.clear();
+    }
+
+    /// Returns the shortest path from `start` to `end`, or `None` if no path
+    /// is found. Results are computed with `astar` the first time a pair is
+    /// requested, and reused until a change to the grid could affect them.
+    pub fn find_path(&mut self, start: Point, end: Point) -> Option<&[Point]> {
+        let grid = &self.grid;
+        self.paths
+            .entry((start, end))
+            .or_insert_with(|| astar(grid, start, end))
+            .as_deref()
+    }
+
+    /// Sets the value of the node at (x, y) coordinates, discarding any cached
+    /// results the change could affect.
+    pub fn set(&mut self, x: isize, y: isize, value: bool) {
+        match self.grid.get(x, y) {
+            Some(current) if current != value => self.grid.set(x, y, value),
+            _ => return,
+        }
+
+        let point = Point::new(x, y);
+
+        if value {
+            self.paths
+                .retain(|_, path| path.as_ref().is_none_or(|path| !path.contains(&point)));
+        } else {
+            self.paths.retain(|(start, end), path| {
+                path.as_ref().is_some_and(|path| {
+                    let detour =
+                        manhattan_distance(start, &point) + manhattan_distance(&point, end);
+                    detour as usize >= path.len() - 1
+                })
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_caches_paths() {
+        let mut cache = PathCache::new(Grid::new(5, 5));
+        let start = Point::new(0, 0);
+        let end = Point::new(4, 4);
+
+        let path = cache.find_path(start, end).unwrap().to_vec();
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.find_path(start, end), Some(path.as_slice()));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_blocking_invalidates_paths_through_node() {
+        let mut cache = PathCache::new(Grid::new(5, 5));
+        let start = Point::new(0, 0);
+        let end = Point::new(4, 0);
+        let other = (Point::new(0, 4), Point::new(4, 4));
+
+        let blocked = cache.find_path(start, end).unwrap()[2];
+        let _ = cache.find_path(other.0, other.1);
+
+        cache.set(blocked.x, blocked.y, true);
+
+        assert_eq!(cache.len(), 1);
+        assert!(!cache.find_path(start, end).unwrap().contains(&blocked));
+    }
+
+    #[test]
+    fn test_opening_invalidates_improvable_paths() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, true,  false],
+            vec![false, true,  false],
+            vec![false, false, false],
+            vec![true,  true,  false],
+        ]);
+
+        let mut cache = PathCache::new(grid);
+        let start = Point::new(0, 0);
+        let end = Point::new(2, 0);
+
+        assert_eq!(cache.find_path(start, end).map(<[Point]>::len), Some(7));
+        assert!(cache.find_path(start, Point::new(0, 3)).is_none());
+        let _ = cache.find_path(Point::new(2, 2), Point::new(2, 3));
+
+        cache.set(1, 0, false);
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.find_path(start, end).map(<[Point]>::len), Some(3));
+    }
+}
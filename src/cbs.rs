@@ -0,0 +1,189 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{space_time_astar, Grid, Point, ReservationTable};
+
+/// A node of the constraint tree: a set of constraints per agent, and the
+/// shortest paths satisfying them.
+struct ConstraintNode {
+    constraints: Vec<ReservationTable>,
+    paths: Vec<Vec<Point>>,
+    cost: usize,
+}
+
+/// A collision between two agents that must be resolved by constraining one
+/// of them.
+enum Conflict {
+    /// Both agents are at `point` at `time`.
+    Vertex {
+        agents: (usize, usize),
+        point: Point,
+        time: usize,
+    },
+    /// The agents swap places during the step from `time` to `time + 1`.
+    Swap { agents: (usize, usize), time: usize },
+}
+
+/// Plans collision-free timed paths for several agents, given as `(start,
+/// end)` pairs, using Conflict-Based Search. Each returned path lists the
+/// agent's position at every timestep, after which it stays at its end point.
+///
+/// Unlike `cooperative_paths`, the result is optimal: it minimizes the sum of
+/// the agents' arrival times. This is expensive for large numbers of agents,
+/// so the search gives up and returns `None` after expanding
+/// `max_expansions` nodes of the constraint tree. `None` is also returned if
+/// no solution exists.
+#[must_use]
+pub fn conflict_based_search(
+    grid: &Grid,
+    agents: &[(Point, Point)],
+    max_expansions: usize,
+) -> Option<Vec<Vec<Point>>> {
+    let constraints = vec![ReservationTable::new(); agents.len()];
+    let paths = agents
+        .iter()
+        .map(|&(start, end)| space_time_astar(grid, &ReservationTable::new(), start, end))
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut tree = vec![ConstraintNode {
+        cost: sum_of_costs(&paths),
+        constraints,
+        paths,
+    }];
+    let mut open = BinaryHeap::from([(Reverse(tree[0].cost), Reverse(0))]);
+
+    for _ in 0..max_expansions {
+        let (_, Reverse(index)) = open.pop()?;
+
+        let Some(conflict) = find_conflict(&tree[index].paths) else {
+            return Some(std::mem::take(&mut tree[index].paths));
+        };
+
+        let ((first, second), time) = match conflict {
+            Conflict::Vertex { agents, time, .. } | Conflict::Swap { agents, time } => {
+                (agents, time)
+            }
+        };
+
+        for agent in [first, second] {
+            let node = &tree[index];
+            let mut constraints = node.constraints[agent].clone();
+
+            match conflict {
+                Conflict::Vertex { point, .. } => constraints.reserve_node(point, time),
+                Conflict::Swap { .. } => {
+                    let path = &node.paths[agent];
+                    constraints.reserve_edge(position(path, time), position(path, time + 1), time);
+                }
+            }
+
+            let (start, end) = agents[agent];
+            let Some(path) = space_time_astar(grid, &constraints, start, end) else {
+                continue;
+            };
+
+            let mut child = ConstraintNode {
+                constraints: node.constraints.clone(),
+                paths: node.paths.clone(),
+                cost: 0,
+            };
+            child.constraints[agent] = constraints;
+            child.paths[agent] = path;
+            child.cost = sum_of_costs(&child.paths);
+
+            open.push((Reverse(child.cost), Reverse(tree.len())));
+            tree.push(child);
+        }
+
+        // Expanded nodes are never revisited, so free their paths and
+        // constraints early.
+        tree[index].paths = Vec::new();
+        tree[index].constraints = Vec::new();
+    }
+
+    None
+}
+
+/// Returns the first collision between any two paths, if any.
+fn find_conflict(paths: &[Vec<Point>]) -> Option<Conflict> {
+    let max_len = paths.iter().map(Vec::len).max().unwrap_or(0);
+
+    for time in 0..max_len {
+        for a in 0..paths.len() {
+            for b in a + 1..paths.len() {
+                let point = position(&paths[a], time);
+
+                if point == position(&paths[b], time) {
+                    return Some(Conflict::Vertex {
+                        agents: (a, b),
+                        point,
+                        time,
+                    });
+                }
+
+                if point == position(&paths[b], time + 1)
+                    && position(&paths[a], time + 1) == position(&paths[b], time)
+                {
+                    return Some(Conflict::Swap {
+                        agents: (a, b),
+                        time,
+                    });
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns where an agent following `path` is at `time`, staying at the last
+/// point once it has arrived.
+fn position(path: &[Point], time: usize) -> Point {
+    path[time.min(path.len() - 1)]
+}
+
+fn sum_of_costs(paths: &[Vec<Point>]) -> usize {
+    paths.iter().map(|path| path.len() - 1).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cooperative_paths;
+
+    #[test]
+    fn test_resolves_conflicts() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false],
+            vec![true,  false, true,  true ],
+        ]);
+
+        let agents = [
+            (Point::new(0, 0), Point::new(3, 0)),
+            (Point::new(3, 0), Point::new(0, 0)),
+        ];
+
+        // Prioritized planning fails here, as the first agent never makes way.
+        assert!(cooperative_paths(&grid, &agents).is_none());
+
+        let paths = conflict_based_search(&grid, &agents, 1000).unwrap();
+
+        assert!(find_conflict(&paths).is_none());
+        for (path, (start, end)) in paths.iter().zip(agents) {
+            assert_eq!(path.first(), Some(&start));
+            assert_eq!(path.last(), Some(&end));
+        }
+        assert_eq!(sum_of_costs(&paths), 8);
+    }
+
+    #[test]
+    fn test_no_solution() {
+        let grid = Grid::new(2, 1);
+        let agents = [
+            (Point::new(0, 0), Point::new(1, 0)),
+            (Point::new(1, 0), Point::new(0, 0)),
+        ];
+
+        assert!(conflict_based_search(&grid, &agents, 100).is_none());
+    }
+}
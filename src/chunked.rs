@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use crate::{manhattan_distance, retrace_path, search, Map, Node, Point};
+
+/// An unbounded grid split into square chunks that are only allocated once a
+/// node in them is set, for open worlds too large to store as one `Grid`.
+///
+/// Unlike `Grid`, any `Point` is valid, including negative coordinates.
+/// Nodes in chunks that were never allocated hold the default value given to
+/// `ChunkedGrid::new`.
+///
+/// Allocated chunks may lie arbitrarily far apart, so searches run over a
+/// bounded `ChunkedView` of the grid, created with `ChunkedGrid::view`.
+/// `ChunkedGrid::find_path` picks the view for a search automatically.
+#[derive(Debug, Clone)]
+pub struct ChunkedGrid {
+    chunk_size: usize,
+    default: bool,
+    chunks: HashMap<(isize, isize), Vec<bool>>,
+    /// The smallest and largest coordinates of any allocated chunk.
+    extent: Option<((isize, isize), (isize, isize))>,
+}
+
+impl ChunkedGrid {
+    /// Creates an empty grid with chunks of `chunk_size` by `chunk_size`
+    /// nodes, where every node is `default` until set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    #[must_use]
+    pub fn new(chunk_size: usize, default: bool) -> Self {
+        assert!(chunk_size > 0, "chunk size must be greater than zero");
+
+        Self {
+            chunk_size,
+            default,
+            chunks: HashMap::new(),
+            extent: None,
+        }
+    }
+
+    /// Returns the width and height of each chunk.
+    #[must_use]
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Returns the value of nodes in chunks that have not been allocated.
+    #[must_use]
+    pub fn default(&self) -> bool {
+        self.default
+    }
+
+    /// Returns the number of allocated chunks.
+    #[must_use]
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns the value of the node at (x, y) coordinates.
+    #[must_use]
+    pub fn get(&self, x: isize, y: isize) -> bool {
+        let (chunk, index) = self.locate(x, y);
+        self.chunks
+            .get(&chunk)
+            .map_or(self.default, |nodes| nodes[index])
+    }
+
+    /// Sets the value of the node at (x, y) coordinates, allocating its chunk
+    /// if needed. Setting a node in an unallocated chunk to the default value
+    /// allocates nothing.
+    pub fn set(&mut self, x: isize, y: isize, value: bool) {
+        let (chunk, index) = self.locate(x, y);
+
+        if let Some(nodes) = self.chunks.get_mut(&chunk) {
+            nodes[index] = value;
+        } else if value != self.default {
+            let mut nodes = vec![self.default; self.chunk_size * self.chunk_size];
+            nodes[index] = value;
+            self.chunks.insert(chunk, nodes);
+
+            let (min, max) = self.extent.unwrap_or((chunk, chunk));
+            self.extent = Some((
+                (min.0.min(chunk.0), min.1.min(chunk.1)),
+                (max.0.max(chunk.0), max.1.max(chunk.1)),
+            ));
+        }
+    }
+
+    /// Returns whether the node at `point` is walkable.
+    #[must_use]
+    pub fn is_walkable(&self, point: Point) -> bool {
+        !self.get(point.x, point.y)
+    }
+
+    /// Returns the smallest and largest corners of the area covered by
+    /// allocated chunks, or `None` if no chunks are allocated.
+    #[must_use]
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        let size = self.chunk_size as isize;
+        let (min, max) = self.extent?;
+
+        Some((
+            Point::new(min.0 * size, min.1 * size),
+            Point::new((max.0 + 1) * size - 1, (max.1 + 1) * size - 1),
+        ))
+    }
+
+    /// Returns a view of the nodes from `min` to `max`, inclusive, which can
+    /// be searched by `astar` and the other searches over a `Map`. The view
+    /// is empty if `min` lies below or to the right of `max`.
+    #[must_use]
+    pub fn view(&self, min: Point, max: Point) -> ChunkedView<'_> {
+        ChunkedView {
+            grid: self,
+            min,
+            max,
+        }
+    }
+
+    /// Attempts to find the shortest path from `start` to `end` using A*.
+    /// Returns `None` if no path is found.
+    ///
+    /// The search starts in a window around the endpoints and doubles its
+    /// margin until the path found is provably the shortest, or the start or
+    /// end is provably walled in. Distant chunks are never visited, so the
+    /// cost depends on the length of the detour, not on how far apart the
+    /// allocated chunks are.
+    #[must_use]
+    pub fn find_path(&self, start: Point, end: Point) -> Option<Vec<Point>> {
+        if end != start && !self.is_walkable(end) {
+            return None;
+        }
+
+        let min = Point::new(start.x.min(end.x), start.y.min(end.y));
+        let max = Point::new(start.x.max(end.x), start.y.max(end.y));
+        let distance = manhattan_distance(&start, &end);
+
+        // Beyond a border one node wide around the endpoints and allocated
+        // chunks every node holds the default value, so a shortest path never
+        // needs to leave it.
+        let (limit_min, limit_max) = self.bounds().map_or((min, max), |(low, high)| {
+            (
+                Point::new(low.x.min(min.x), low.y.min(min.y)),
+                Point::new(high.x.max(max.x), high.y.max(max.y)),
+            )
+        });
+        let limit_min = Point::new(limit_min.x.saturating_sub(1), limit_min.y.saturating_sub(1));
+        let limit_max = Point::new(limit_max.x.saturating_add(1), limit_max.y.saturating_add(1));
+
+        let mut margin = self.chunk_size as isize;
+
+        loop {
+            let window = self.view(
+                Point::new(
+                    min.x.saturating_sub(margin).max(limit_min.x),
+                    min.y.saturating_sub(margin).max(limit_min.y),
+                ),
+                Point::new(
+                    max.x.saturating_add(margin).min(limit_max.x),
+                    max.y.saturating_add(margin).min(limit_max.y),
+                ),
+            );
+            let complete = window.min == limit_min && window.max == limit_max;
+
+            let forward = search(
+                &window,
+                start,
+                |point| manhattan_distance(&point, &end),
+                |point| point == end,
+            );
+
+            if let Some(index) = forward.found {
+                let path = retrace_path(&forward.nodes, index);
+
+                // Any path leaving the window travels at least `margin + 1`
+                // nodes past the endpoints and back.
+                let cost = path.len() as isize - 1;
+                if complete || cost <= distance + 2 * (margin + 1) {
+                    return Some(path);
+                }
+            } else if complete || !window.reached_edge(&forward.nodes) {
+                return None;
+            } else {
+                let backward = search(
+                    &window,
+                    end,
+                    |point| manhattan_distance(&point, &start),
+                    |point| point == start,
+                );
+
+                if !window.reached_edge(&backward.nodes) {
+                    return None;
+                }
+            }
+
+            margin = margin.saturating_mul(2);
+        }
+    }
+
+    /// Returns the coordinates of the chunk containing (x, y), and the index
+    /// of the node within that chunk.
+    fn locate(&self, x: isize, y: isize) -> ((isize, isize), usize) {
+        let size = self.chunk_size as isize;
+        let chunk = (x.div_euclid(size), y.div_euclid(size));
+        let index = y.rem_euclid(size) as usize * self.chunk_size + x.rem_euclid(size) as usize;
+
+        (chunk, index)
+    }
+}
+
+/// A rectangular view of a `ChunkedGrid`, which may extend past its
+/// allocated chunks. Created by `ChunkedGrid::view`.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkedView<'a> {
+    grid: &'a ChunkedGrid,
+    min: Point,
+    max: Point,
+}
+
+impl ChunkedView<'_> {
+    /// Returns whether any of `nodes` lies on the outermost ring of the
+    /// window. If none do, the search that created them was walled in.
+    fn reached_edge(&self, nodes: &[Node]) -> bool {
+        nodes.iter().any(|node| {
+            let point = node.point;
+            point.x == self.min.x
+                || point.y == self.min.y
+                || point.x == self.max.x
+                || point.y == self.max.y
+        })
+    }
+}
+
+impl Map for ChunkedView<'_> {
+    fn width(&self) -> usize {
+        (self.max.x - self.min.x + 1).max(0) as usize
+    }
+
+    fn height(&self) -> usize {
+        (self.max.y - self.min.y + 1).max(0) as usize
+    }
+
+    fn origin(&self) -> Point {
+        self.min
+    }
+
+    fn is_walkable(&self, point: Point) -> bool {
+        self.grid.is_walkable(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar;
+
+    #[test]
+    fn test_negative_coordinates() {
+        let mut grid = ChunkedGrid::new(8, false);
+        assert!(!grid.get(-100, 250));
+
+        grid.set(-1, -1, true);
+        grid.set(-8, 7, true);
+        grid.set(0, 0, false);
+
+        assert!(grid.get(-1, -1));
+        assert!(grid.get(-8, 7));
+        assert!(!grid.get(0, 0));
+        assert!(!grid.get(-9, 7));
+        assert_eq!(grid.chunk_count(), 2);
+        assert_eq!(grid.bounds(), Some((Point::new(-8, -8), Point::new(-1, 7))));
+    }
+
+    #[test]
+    fn test_view() {
+        let mut grid = ChunkedGrid::new(4, false);
+
+        // A wall from (-3, -3) to (-3, 3) between the endpoints.
+        for y in -3..=3 {
+            grid.set(-3, y, true);
+        }
+
+        let start = Point::new(-6, 0);
+        let end = Point::new(0, 0);
+
+        let view = grid.view(Point::new(-8, -5), Point::new(1, 5));
+        assert_eq!(view.origin(), Point::new(-8, -5));
+        assert_eq!((view.width(), view.height()), (10, 11));
+
+        let path = astar(&view, start, end).unwrap();
+        assert_eq!(path.len(), 15);
+        assert_eq!(Some(path), grid.find_path(start, end));
+
+        // Too narrow a view to get around the wall.
+        let view = grid.view(Point::new(-8, -3), Point::new(1, 3));
+        assert_eq!(astar(&view, start, end), None);
+
+        let empty = grid.view(Point::new(0, 0), Point::new(-1, 5));
+        assert_eq!(empty.width(), 0);
+    }
+
+    #[test]
+    fn test_default_walls() {
+        let mut grid = ChunkedGrid::new(4, true);
+        assert!(!grid.is_walkable(Point::new(3, 3)));
+
+        for x in -6..=6 {
+            grid.set(x, -2, false);
+        }
+
+        assert_eq!(grid.chunk_count(), 4);
+        assert_eq!(
+            grid.find_path(Point::new(-6, -2), Point::new(6, -2))
+                .map(|path| path.len()),
+            Some(13)
+        );
+        assert_eq!(grid.find_path(Point::new(-6, -2), Point::new(6, -1)), None);
+    }
+
+    #[test]
+    fn test_path_around_walls() {
+        let mut grid = ChunkedGrid::new(4, false);
+
+        // A wall from (-3, -3) to (-3, 3) between the endpoints.
+        for y in -3..=3 {
+            grid.set(-3, y, true);
+        }
+
+        let start = Point::new(-6, 0);
+        let end = Point::new(0, 0);
+        let path = grid.find_path(start, end).unwrap();
+
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&end));
+        assert_eq!(path.len(), 6 + 2 * 4 + 1);
+        assert!(path.iter().all(|&point| grid.is_walkable(point)));
+    }
+
+    #[test]
+    fn test_distant_chunks() {
+        let mut grid = ChunkedGrid::new(16, false);
+        grid.set(-(1 << 36), 0, true);
+        grid.set(1 << 36, 0, true);
+
+        let path = grid.find_path(Point::new(0, 0), Point::new(3, 0));
+        assert_eq!(path.map(|path| path.len()), Some(4));
+
+        // Wall in the end, which must not flood the space between the chunks.
+        for (dx, dy) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
+            grid.set(20 + dx, 20 + dy, true);
+        }
+
+        assert_eq!(grid.find_path(Point::new(0, 0), Point::new(20, 20)), None);
+        assert_eq!(grid.find_path(Point::new(20, 20), Point::new(0, 0)), None);
+    }
+
+    #[test]
+    fn test_long_detour() {
+        let mut grid = ChunkedGrid::new(4, false);
+
+        // A wall far longer than the first window, with a gap at its end.
+        for y in -40..40 {
+            grid.set(0, y, true);
+        }
+
+        let path = grid.find_path(Point::new(-1, 0), Point::new(1, 0)).unwrap();
+        assert_eq!(path.len(), 2 * 40 + 3);
+        assert!(path.iter().all(|&point| grid.is_walkable(point)));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{manhattan_distance, retrace_path, search_filtered, Grid, Point};
+
+/// The clearance of every node in a `Grid`: the size of the largest square of
+/// walkable nodes with its top-left corner at that node. Blocked nodes have a
+/// clearance of zero.
+///
+/// A `Clearance` must only be used with the grid it was computed from. After
+/// changing a node, call `Clearance::update` to recompute the nodes it
+/// affects.
+#[derive(Debug, Clone)]
+pub struct Clearance {
+    width: usize,
+    height: usize,
+    values: Vec<usize>,
+}
+
+impl Clearance {
+    /// Computes the clearance of every node in `grid`.
+    #[must_use]
+    pub fn new(grid: &Grid) -> Self {
+        let mut clearance = Self {
+            width: grid.width(),
+            height: grid.height(),
+            values: vec![0; grid.width() * grid.height()],
+        };
+
+        for y in (0..grid.height() as isize).rev() {
+            for x in (0..grid.width() as isize).rev() {
+                clearance.recompute(grid, x, y);
+            }
+        }
+
+        clearance
+    }
+
+    /// Returns the clearance of the node at a given `Point`, or zero if it is
+    /// outside of the grid.
+    #[must_use]
+    pub fn get(&self, point: Point) -> usize {
+        self.index(point.x, point.y).map_or(0, |i| self.values[i])
+    }
+
+    /// Recomputes the clearance of the nodes affected by a change to the node
+    /// at `point`, which are only ever above and to the left of it.
+    pub fn update(&mut self, grid: &Grid, point: Point) {
+        let (x, y) = (point.x, point.y);
+        if self.index(x, y).is_none() {
+            return;
+        }
+
+        // Works outwards in L-shaped rings around the changed node, each
+        // depending only on the rings before it. Once a ring is unchanged,
+        // no node further out can change either.
+        for distance in 0.. {
+            let column = x - distance;
+            let row = y - distance;
+
+            if column < 0 && row < 0 {
+                break;
+            }
+
+            let mut changed = false;
+
+            if column >= 0 {
+                for ring_y in ((row + 1).max(0)..=y).rev() {
+                    changed |= self.recompute(grid, column, ring_y);
+                }
+            }
+            if row >= 0 {
+                for ring_x in ((column + 1).max(0)..=x).rev() {
+                    changed |= self.recompute(grid, ring_x, row);
+                }
+            }
+            if column >= 0 && row >= 0 {
+                changed |= self.recompute(grid, column, row);
+            }
+
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    /// Recomputes the clearance of a single node from its neighbors to the
+    /// right and below, returning whether it changed.
+    fn recompute(&mut self, grid: &Grid, x: isize, y: isize) -> bool {
+        let Some(index) = self.index(x, y) else {
+            return false;
+        };
+
+        let value = if grid.is_walkable(Point::new(x, y)) {
+            let right = self.get(Point::new(x + 1, y));
+            let below = self.get(Point::new(x, y + 1));
+            let diagonal = self.get(Point::new(x + 1, y + 1));
+
+            1 + right.min(below).min(diagonal)
+        } else {
+            0
+        };
+
+        let changed = self.values[index] != value;
+        self.values[index] = value;
+        changed
+    }
+
+    fn index(&self, x: isize, y: isize) -> Option<usize> {
+        let in_bounds = x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height;
+
+        in_bounds.then(|| y as usize * self.width + x as usize)
+    }
+}
+
+/// Attempts to find the shortest path from `start` to `end` for an agent
+/// occupying a square of `size` by `size` nodes. Each point of the path is
+/// the top-left corner of the agent, and is only used if `clearance` shows
+/// the whole square fits there. Returns `None` if no path is found.
+///
+/// An agent with a `size` of one follows the same paths as `astar`.
+#[must_use]
+pub fn astar_sized(
+    grid: &Grid,
+    clearance: &Clearance,
+    start: Point,
+    end: Point,
+    size: usize,
+) -> Option<Vec<Point>> {
+    if clearance.get(end) < size {
+        return None;
+    }
+
+    let result = search_filtered(
+        grid,
+        start,
+        |point| manhattan_distance(&point, &end),
+        |point| point == end,
+        |point| clearance.get(point) >= size,
+    );
+
+    Some(retrace_path(&result.nodes, result.found?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clearance() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false],
+            vec![false, false, true ],
+            vec![false, false, false],
+        ]);
+
+        let clearance = Clearance::new(&grid);
+
+        assert_eq!(clearance.get(Point::new(0, 0)), 2);
+        assert_eq!(clearance.get(Point::new(1, 0)), 1);
+        assert_eq!(clearance.get(Point::new(2, 1)), 0);
+        assert_eq!(clearance.get(Point::new(0, 1)), 2);
+        assert_eq!(clearance.get(Point::new(5, 5)), 0);
+    }
+
+    #[test]
+    fn test_update_matches_rebuild() {
+        let mut grid = Grid::new(8, 8);
+        let mut clearance = Clearance::new(&grid);
+
+        for (x, y, value) in [(5, 5, true), (2, 6, true), (5, 5, false), (0, 0, true)] {
+            grid.set(x, y, value);
+            clearance.update(&grid, Point::new(x, y));
+
+            assert_eq!(clearance.values, Clearance::new(&grid).values);
+        }
+    }
+
+    #[test]
+    fn test_large_agent_avoids_gaps() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false, false, false],
+            vec![false, false, false, false, false, false],
+            vec![true,  true,  false, true,  true,  true ],
+            vec![false, false, false, false, false, false],
+            vec![false, false, false, false, false, false],
+            vec![true,  true,  true,  false, false, true ],
+            vec![false, false, false, false, false, false],
+            vec![false, false, false, false, false, false],
+        ]);
+
+        let clearance = Clearance::new(&grid);
+        let start = Point::new(0, 0);
+        let end = Point::new(0, 6);
+
+        assert!(astar_sized(&grid, &clearance, start, end, 1).is_some());
+        assert!(astar_sized(&grid, &clearance, start, end, 2).is_none());
+
+        let path = astar_sized(&grid, &clearance, Point::new(0, 3), end, 2).unwrap();
+        assert!(path.iter().all(|&point| clearance.get(point) >= 2));
+        assert!(path.contains(&Point::new(3, 4)));
+    }
+}
@@ -0,0 +1,307 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::{get_neighbor_points, manhattan_distance, Grid, Point};
+
+/// Records which nodes and edges are occupied at each timestep, so that
+/// agents planned later can avoid those planned earlier.
+///
+/// Time is measured in steps: an agent following a timed path is at
+/// `path[t]` at time `t`, and moving to an adjacent node or waiting in place
+/// both take one step.
+#[derive(Debug, Clone, Default)]
+pub struct ReservationTable {
+    nodes: HashSet<(Point, usize)>,
+    edges: HashSet<(Point, Point, usize)>,
+    /// Nodes reserved from a given time onwards, such as a goal an agent
+    /// stays at after arriving.
+    permanent: HashMap<Point, usize>,
+    /// The latest time each node is reserved at, not counting permanent
+    /// reservations.
+    latest: HashMap<Point, usize>,
+    horizon: usize,
+}
+
+impl ReservationTable {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves `point` at `time`.
+    pub fn reserve_node(&mut self, point: Point, time: usize) {
+        self.nodes.insert((point, time));
+        self.horizon = self.horizon.max(time);
+
+        let latest = self.latest.entry(point).or_default();
+        *latest = (*latest).max(time);
+    }
+
+    /// Reserves the edge between `from` and `to`, in both directions, for the
+    /// step from `time` to `time + 1`. This prevents two agents from swapping
+    /// places by moving through each other.
+    pub fn reserve_edge(&mut self, from: Point, to: Point, time: usize) {
+        self.edges.insert((from, to, time));
+        self.edges.insert((to, from, time));
+        self.horizon = self.horizon.max(time + 1);
+    }
+
+    /// Reserves `point` at `time` and every time after it.
+    pub fn reserve_from(&mut self, point: Point, time: usize) {
+        let start = self.permanent.entry(point).or_insert(time);
+        *start = (*start).min(time);
+        self.horizon = self.horizon.max(time);
+    }
+
+    /// Reserves every node and edge along a timed path, and its last point
+    /// from the time it is reached onwards.
+    pub fn reserve_path(&mut self, path: &[Point]) {
+        for (time, &point) in path.iter().enumerate() {
+            self.reserve_node(point, time);
+        }
+
+        for (time, pair) in path.windows(2).enumerate() {
+            if pair[0] != pair[1] {
+                self.reserve_edge(pair[0], pair[1], time);
+            }
+        }
+
+        if let Some(&last) = path.last() {
+            self.reserve_from(last, path.len() - 1);
+        }
+    }
+
+    /// Returns whether `point` is reserved at `time`.
+    #[must_use]
+    pub fn is_node_reserved(&self, point: Point, time: usize) -> bool {
+        self.nodes.contains(&(point, time))
+            || self
+                .permanent
+                .get(&point)
+                .is_some_and(|&start| start <= time)
+    }
+
+    /// Returns whether moving from `from` to `to` during the step from `time`
+    /// to `time + 1` is reserved.
+    #[must_use]
+    pub fn is_edge_reserved(&self, from: Point, to: Point, time: usize) -> bool {
+        self.edges.contains(&(from, to, time))
+    }
+
+    /// Returns whether `point` is free at `time` and at every time after it,
+    /// so an agent can stop there.
+    #[must_use]
+    pub fn is_free_from(&self, point: Point, time: usize) -> bool {
+        !self.permanent.contains_key(&point) && self.latest.get(&point).is_none_or(|&t| t < time)
+    }
+
+    /// Returns the last time at which anything other than a permanent
+    /// reservation changes.
+    #[must_use]
+    pub fn horizon(&self) -> usize {
+        self.horizon
+    }
+
+    /// Removes every reservation.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Attempts to find the shortest timed path from `start` to `end` that avoids
+/// every reservation in `table`, where `path[t]` is the position at time `t`.
+/// Agents may wait in place, which repeats a point in the path. The path only
+/// ends once `end` stays free forever, so the agent can remain there.
+///
+/// Returns `None` if no such path exists.
+#[must_use]
+pub fn space_time_astar(
+    grid: &Grid,
+    table: &ReservationTable,
+    start: Point,
+    end: Point,
+) -> Option<Vec<Point>> {
+    grid.index(start.x, start.y)?;
+    grid.index(end.x, end.y)?;
+
+    // After the horizon only permanent reservations remain, which never
+    // change, so later times can share closed states.
+    let settled = table.horizon() + 1;
+
+    let mut nodes = vec![(start, 0, usize::MAX)];
+    let mut closed = HashSet::new();
+    let mut open = BinaryHeap::from([(Reverse(manhattan_distance(&start, &end) as usize), 0)]);
+
+    while let Some((_, index)) = open.pop() {
+        let (point, time, _) = nodes[index];
+
+        if !closed.insert((point, time.min(settled))) {
+            continue;
+        }
+
+        if point == end && table.is_free_from(end, time) {
+            let mut path = Vec::with_capacity(time + 1);
+            let mut current = index;
+            while current != usize::MAX {
+                path.push(nodes[current].0);
+                current = nodes[current].2;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let next_time = time + 1;
+        let moves = get_neighbor_points(grid, point).chain(std::iter::once(point));
+
+        for next in moves {
+            if closed.contains(&(next, next_time.min(settled)))
+                || table.is_node_reserved(next, next_time)
+                || table.is_edge_reserved(point, next, time)
+            {
+                continue;
+            }
+
+            nodes.push((next, next_time, index));
+            let f = next_time + manhattan_distance(&next, &end) as usize;
+            open.push((Reverse(f), nodes.len() - 1));
+        }
+    }
+
+    None
+}
+
+/// Attempts to find the shortest timed path from `start` to `end` that never
+/// collides with moving obstacles whose routes are known in advance, such as
+/// patrolling guards. Each obstacle is given as its position at every
+/// timestep, after which it stays at its last point.
+///
+/// The agent may wait in place to let obstacles pass, and never swaps places
+/// with one. Like `space_time_astar`, the path only ends once no obstacle
+/// will enter `end` afterwards. Returns `None` if no such path exists.
+#[must_use]
+pub fn avoid_moving_obstacles(
+    grid: &Grid,
+    start: Point,
+    end: Point,
+    obstacles: &[Vec<Point>],
+) -> Option<Vec<Point>> {
+    let mut table = ReservationTable::new();
+    for obstacle in obstacles {
+        table.reserve_path(obstacle);
+    }
+
+    space_time_astar(grid, &table, start, end)
+}
+
+/// Plans collision-free timed paths for several agents, given as `(start,
+/// end)` pairs in priority order. Each agent is planned with
+/// `space_time_astar` around the reservations of the agents before it, and
+/// then stays at its end point.
+///
+/// Returns `None` if any agent cannot reach its end point. Prioritized
+/// planning is fast but not complete, so reordering the agents may help.
+#[must_use]
+pub fn cooperative_paths(grid: &Grid, agents: &[(Point, Point)]) -> Option<Vec<Vec<Point>>> {
+    let mut table = ReservationTable::new();
+    let mut paths = Vec::with_capacity(agents.len());
+
+    for &(start, end) in agents {
+        let path = space_time_astar(grid, &table, start, end)?;
+        table.reserve_path(&path);
+        paths.push(path);
+    }
+
+    Some(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_no_collisions(paths: &[Vec<Point>]) {
+        let max_len = paths.iter().map(Vec::len).max().unwrap_or(0);
+        let at = |path: &Vec<Point>, t: usize| path[t.min(path.len() - 1)];
+
+        for t in 0..max_len {
+            for (i, a) in paths.iter().enumerate() {
+                for b in &paths[i + 1..] {
+                    assert_ne!(at(a, t), at(b, t), "Vertex collision at time {t}");
+                    assert!(
+                        !(at(a, t) == at(b, t + 1) && at(a, t + 1) == at(b, t)),
+                        "Swap collision at time {t}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_waits_for_reservation() {
+        let grid = Grid::new(3, 1);
+        let mut table = ReservationTable::new();
+        table.reserve_node(Point::new(1, 0), 1);
+
+        let path = space_time_astar(&grid, &table, Point::new(0, 0), Point::new(2, 0)).unwrap();
+
+        assert_eq!(
+            path,
+            vec![
+                Point::new(0, 0),
+                Point::new(0, 0),
+                Point::new(1, 0),
+                Point::new(2, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_crossing_agents() {
+        let grid = Grid::new(3, 3);
+        let agents = [
+            (Point::new(0, 1), Point::new(2, 1)),
+            (Point::new(1, 0), Point::new(1, 2)),
+            (Point::new(2, 2), Point::new(0, 0)),
+        ];
+
+        let paths = cooperative_paths(&grid, &agents).unwrap();
+
+        for (path, (start, end)) in paths.iter().zip(agents) {
+            assert_eq!(path.first(), Some(&start));
+            assert_eq!(path.last(), Some(&end));
+        }
+        assert_no_collisions(&paths);
+    }
+
+    #[test]
+    fn test_moving_obstacle() {
+        let grid = Grid::new(5, 2);
+        let start = Point::new(0, 0);
+        let end = Point::new(4, 0);
+
+        // A guard walks along the top row towards the agent, then steps aside.
+        let guards = [vec![
+            Point::new(3, 0),
+            Point::new(2, 0),
+            Point::new(1, 0),
+            Point::new(1, 1),
+        ]];
+
+        let path = avoid_moving_obstacles(&grid, start, end, &guards).unwrap();
+
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&end));
+        assert!(path.len() > 5);
+        assert_no_collisions(&[path, guards[0].clone()]);
+    }
+
+    #[test]
+    fn test_blocked_goal() {
+        let grid = Grid::new(2, 1);
+        let mut table = ReservationTable::new();
+        table.reserve_from(Point::new(1, 0), 5);
+
+        assert!(space_time_astar(&grid, &table, Point::new(0, 0), Point::new(1, 0)).is_none());
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+};
+
+use crate::{Grid, Point, NEIGHBORS};
+
+/// Identifies the file format written by `PathDatabase::write_to`.
+const MAGIC: &[u8; 4] = b"SCPD";
+const VERSION: u8 = 1;
+
+/// Region label of blocked nodes.
+const BLOCKED: u32 = u32::MAX;
+
+/// A compressed path database (CPD) for a grid that never changes, such as a
+/// map shipped with a game.
+///
+/// Building the database runs a breadth-first search from every walkable
+/// node and records the first move of a shortest path towards every other
+/// node, which takes time quadratic in the size of the grid. In exchange,
+/// queries follow the recorded moves without searching at all.
+///
+/// Each table of first moves is run-length compressed. Targets that cannot be
+/// reached never need a move, so they extend whichever run they fall in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathDatabase {
+    width: usize,
+    height: usize,
+    /// Connected-region label of every node, which rejects unreachable
+    /// queries before any first moves are followed.
+    regions: Vec<u32>,
+    /// Runs of first moves for every source node, ordered by target index.
+    rows: Vec<Vec<Run>>,
+}
+
+/// A run of consecutive target indices sharing the same first move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Run {
+    start: u32,
+    direction: u8,
+}
+
+impl PathDatabase {
+    /// Builds the database for every pair of walkable nodes in `grid`.
+    #[must_use]
+    pub fn build(grid: &Grid) -> Self {
+        let width = grid.width();
+        let height = grid.height();
+        let capacity = width * height;
+
+        let mut database = Self {
+            width,
+            height,
+            regions: vec![BLOCKED; capacity],
+            rows: vec![Vec::new(); capacity],
+        };
+
+        let mut next_region = 0;
+        let mut directions = vec![None; capacity];
+        let mut queue = VecDeque::new();
+
+        for source in 0..capacity {
+            let source_point = database.point(source);
+            if !grid.is_walkable(source_point) {
+                continue;
+            }
+
+            let new_region = database.regions[source] == BLOCKED;
+            if new_region {
+                database.regions[source] = next_region;
+            }
+
+            directions.fill(None);
+            queue.clear();
+            queue.push_back(source);
+
+            while let Some(index) = queue.pop_front() {
+                let point = database.point(index);
+
+                for (direction, &(dx, dy)) in NEIGHBORS.iter().enumerate() {
+                    let neighbor = Point::new(point.x + dx, point.y + dy);
+                    if !grid.is_walkable(neighbor) {
+                        continue;
+                    }
+
+                    let neighbor_index = database.index(neighbor);
+                    if neighbor_index == source || directions[neighbor_index].is_some() {
+                        continue;
+                    }
+
+                    directions[neighbor_index] = if index == source {
+                        Some(direction as u8)
+                    } else {
+                        directions[index]
+                    };
+
+                    if new_region {
+                        database.regions[neighbor_index] = next_region;
+                    }
+
+                    queue.push_back(neighbor_index);
+                }
+            }
+
+            if new_region {
+                next_region += 1;
+            }
+
+            database.rows[source] = compress(&directions);
+        }
+
+        database
+    }
+
+    /// Returns the width of the grid the database was built from.
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the grid the database was built from.
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the next point along a shortest path from `start` to `end`, or
+    /// `None` if `end` is unreachable or equal to `start`.
+    #[must_use]
+    pub fn first_move(&self, start: Point, end: Point) -> Option<Point> {
+        let start_index = self.checked_index(start)?;
+        let end_index = self.checked_index(end)?;
+
+        if start_index == end_index
+            || self.regions[start_index] == BLOCKED
+            || self.regions[start_index] != self.regions[end_index]
+        {
+            return None;
+        }
+
+        let row = &self.rows[start_index];
+        let run = row.partition_point(|run| run.start as usize <= end_index);
+        let (dx, dy) = NEIGHBORS[row[run.checked_sub(1)?].direction as usize];
+
+        Some(Point::new(start.x + dx, start.y + dy))
+    }
+
+    /// Returns a shortest path from `start` to `end` by following first moves,
+    /// or `None` if no path exists.
+    ///
+    /// Also returns `None` if the moves revisit a node, which can only happen
+    /// when a corrupt file was loaded with `PathDatabase::read_from`.
+    #[must_use]
+    pub fn find_path(&self, start: Point, end: Point) -> Option<Vec<Point>> {
+        let start_index = self.checked_index(start)?;
+        if self.regions[start_index] == BLOCKED {
+            return None;
+        }
+
+        let mut path = vec![start];
+        let mut current = start;
+
+        while current != end {
+            // A shortest path never visits more nodes than the grid holds.
+            if path.len() >= self.width * self.height {
+                return None;
+            }
+
+            current = self.first_move(current, end)?;
+            path.push(current);
+        }
+
+        Some(path)
+    }
+
+    /// Writes the database in a compact binary format, which can be loaded
+    /// again with `PathDatabase::read_from`.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error produced by `writer`.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        write_u32(&mut writer, self.width as u32)?;
+        write_u32(&mut writer, self.height as u32)?;
+
+        for &region in &self.regions {
+            write_u32(&mut writer, region)?;
+        }
+
+        for row in &self.rows {
+            write_u32(&mut writer, row.len() as u32)?;
+            for run in row {
+                write_u32(&mut writer, run.start)?;
+                writer.write_all(&[run.direction])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads a database written by `PathDatabase::write_to`.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error produced by `reader`, or an error of kind
+    /// `InvalidData` if the data is not a valid database.
+    pub fn read_from(mut reader: impl Read) -> io::Result<Self> {
+        let mut header = [0; 5];
+        reader.read_exact(&mut header)?;
+
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("not a path database"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data("unsupported path database version"));
+        }
+
+        let width = read_u32(&mut reader)? as usize;
+        let height = read_u32(&mut reader)? as usize;
+        let capacity = width
+            .checked_mul(height)
+            .ok_or_else(|| invalid_data("grid dimensions overflow"))?;
+
+        let regions = (0..capacity)
+            .map(|_| read_u32(&mut reader))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut rows = Vec::with_capacity(capacity);
+        for _ in 0..capacity {
+            let len = read_u32(&mut reader)? as usize;
+            let mut row: Vec<Run> = Vec::new();
+
+            for _ in 0..len {
+                let start = read_u32(&mut reader)?;
+                let mut direction = [0];
+                reader.read_exact(&mut direction)?;
+
+                let in_order = row.last().map_or(start == 0, |last| last.start < start);
+                if !in_order || start as usize >= capacity {
+                    return Err(invalid_data("runs out of order"));
+                }
+                if direction[0] as usize >= NEIGHBORS.len() {
+                    return Err(invalid_data("invalid direction"));
+                }
+
+                row.push(Run {
+                    start,
+                    direction: direction[0],
+                });
+            }
+
+            rows.push(row);
+        }
+
+        Ok(Self {
+            width,
+            height,
+            regions,
+            rows,
+        })
+    }
+
+    fn checked_index(&self, point: Point) -> Option<usize> {
+        let in_bounds = point.x >= 0
+            && point.y >= 0
+            && (point.x as usize) < self.width
+            && (point.y as usize) < self.height;
+
+        in_bounds.then(|| self.index(point))
+    }
+
+    fn index(&self, point: Point) -> usize {
+        point.y as usize * self.width + point.x as usize
+    }
+
+    fn point(&self, index: usize) -> Point {
+        Point::new((index % self.width) as isize, (index / self.width) as isize)
+    }
+}
+
+/// Run-length encodes a table of first moves, letting targets without a
+/// move join whichever run precedes them.
+fn compress(directions: &[Option<u8>]) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+
+    for (target, &direction) in directions.iter().enumerate() {
+        let Some(direction) = direction else {
+            continue;
+        };
+
+        match runs.last() {
+            Some(run) if run.direction == direction => {}
+            // The first run always starts at zero, covering any leading
+            // targets without a move.
+            None => runs.push(Run {
+                start: 0,
+                direction,
+            }),
+            Some(_) => runs.push(Run {
+                start: target as u32,
+                direction,
+            }),
+        }
+    }
+
+    runs
+}
+
+fn write_u32(writer: &mut impl Write, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar;
+
+    #[test]
+    fn test_matches_astar() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false, false],
+            vec![true,  true,  true,  false, false],
+            vec![false, false, false, false, false],
+            vec![false, true,  true,  true,  true ],
+            vec![false, false, false, false, true ],
+        ]);
+
+        let database = PathDatabase::build(&grid);
+
+        for start_y in 0..5 {
+            for start_x in 0..5 {
+                for end_y in 0..5 {
+                    for end_x in 0..5 {
+                        let start = Point::new(start_x, start_y);
+                        let end = Point::new(end_x, end_y);
+
+                        assert_eq!(
+                            database.find_path(start, end).map(|path| path.len()),
+                            astar(&grid, start, end)
+                                .filter(|_| grid.is_walkable(start) && grid.is_walkable(end))
+                                .map(|path| path.len()),
+                            "Mismatch from {start} to {end}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut grid = Grid::new(6, 4);
+        grid.set(2, 1, true);
+        grid.set(2, 2, true);
+        grid.set(5, 0, true);
+
+        let database = PathDatabase::build(&grid);
+
+        let mut bytes = Vec::new();
+        database.write_to(&mut bytes).unwrap();
+        let loaded = PathDatabase::read_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(loaded, database);
+
+        bytes[0] = b'X';
+        let error = PathDatabase::read_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_cyclic_moves() {
+        let grid = Grid::new(3, 1);
+        let mut database = PathDatabase::build(&grid);
+
+        // Point the middle node back towards the start for every target.
+        database.rows[1] = vec![Run {
+            start: 0,
+            direction: 3,
+        }];
+
+        assert_eq!(database.find_path(Point::new(0, 0), Point::new(2, 0)), None);
+    }
+}
@@ -0,0 +1,300 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{manhattan_distance, Grid, Point, NEIGHBORS};
+
+/// Priority of a node in the open set; compared lexicographically.
+type Key = (usize, usize);
+
+const INFINITY: usize = usize::MAX;
+
+/// An incremental planner using the D* Lite algorithm, for agents that
+/// discover changes to the grid as they move towards a fixed goal.
+///
+/// The planner keeps its search state between calls. After moving the agent
+/// or changing nodes of the grid, call `DStarLite::update` with the new
+/// position and the changed points; only the part of the search affected by
+/// the changes is repaired, which is usually far cheaper than a fresh `astar`
+/// call.
+#[derive(Debug, Clone)]
+pub struct DStarLite {
+    width: usize,
+    height: usize,
+    start: Point,
+    goal: Point,
+    /// The start position when the heuristic was last rebased.
+    last_start: Point,
+    /// Accumulated heuristic offset, which avoids reordering the open set
+    /// whenever the start moves.
+    key_modifier: usize,
+    g_scores: Vec<usize>,
+    rhs_scores: Vec<usize>,
+    open_nodes: BinaryHeap<Reverse<(Key, usize)>>,
+    /// The key each node is currently queued with, used to skip stale
+    /// entries in `open_nodes`.
+    open_keys: Vec<Option<Key>>,
+}
+
+impl DStarLite {
+    /// Creates a planner and computes the initial shortest path from `start`
+    /// to `goal`.
+    #[must_use]
+    pub fn new(grid: &Grid, start: Point, goal: Point) -> Self {
+        let capacity = grid.width() * grid.height();
+
+        let mut planner = Self {
+            width: grid.width(),
+            height: grid.height(),
+            start,
+            goal,
+            last_start: start,
+            key_modifier: 0,
+            g_scores: vec![INFINITY; capacity],
+            rhs_scores: vec![INFINITY; capacity],
+            open_nodes: BinaryHeap::new(),
+            open_keys: vec![None; capacity],
+        };
+
+        if let Some(goal_index) = planner.index(goal) {
+            planner.rhs_scores[goal_index] = 0;
+            planner.push(goal_index);
+        }
+
+        planner.compute_shortest_path(grid);
+        planner
+    }
+
+    /// Returns the current position of the agent.
+    #[must_use]
+    pub fn start(&self) -> Point {
+        self.start
+    }
+
+    /// Returns the goal the planner is searching towards.
+    #[must_use]
+    pub fn goal(&self) -> Point {
+        self.goal
+    }
+
+    /// Moves the agent to `start` and repairs the shortest path after the
+    /// nodes at `changed` were modified in `grid`.
+    pub fn update(&mut self, grid: &Grid, start: Point, changed: &[Point]) {
+        self.key_modifier = self
+            .key_modifier
+            .saturating_add(manhattan_distance(&self.last_start, &start) as usize);
+        self.last_start = start;
+        self.start = start;
+
+        for &point in changed {
+            // A changed node alters the cost of every edge touching it, which
+            // affects its own score and the scores of its neighbors.
+            for (dx, dy) in [(0, 0)].into_iter().chain(NEIGHBORS) {
+                if let Some(index) = self.index(Point::new(point.x + dx, point.y + dy)) {
+                    self.update_node(grid, index);
+                }
+            }
+        }
+
+        self.compute_shortest_path(grid);
+    }
+
+    /// Returns the current shortest path from the agent's position to the
+    /// goal, or `None` if the goal is unreachable.
+    #[must_use]
+    pub fn path(&self, grid: &Grid) -> Option<Vec<Point>> {
+        let start_index = self.index(self.start)?;
+        if self.g_scores[start_index] == INFINITY {
+            return None;
+        }
+
+        let mut path = vec![self.start];
+        let mut current = self.start;
+
+        while current != self.goal {
+            let (next, cost) = self
+                .neighbors(current)
+                .map(|(point, index)| {
+                    (
+                        point,
+                        self.cost(grid, current, point)
+                            .saturating_add(self.g_scores[index]),
+                    )
+                })
+                .min_by_key(|&(_, cost)| cost)?;
+
+            if cost == INFINITY || path.len() > self.g_scores.len() {
+                return None;
+            }
+
+            path.push(next);
+            current = next;
+        }
+
+        Some(path)
+    }
+
+    fn compute_shortest_path(&mut self, grid: &Grid) {
+        let Some(start_index) = self.index(self.start) else {
+            return;
+        };
+
+        while let Some(Reverse((key, index))) = self.open_nodes.peek().copied() {
+            if self.open_keys[index] != Some(key) {
+                self.open_nodes.pop();
+                continue;
+            }
+
+            let start_consistent = self.g_scores[start_index] == self.rhs_scores[start_index];
+            if key >= self.key(start_index) && start_consistent {
+                break;
+            }
+
+            self.open_nodes.pop();
+            self.open_keys[index] = None;
+
+            let new_key = self.key(index);
+            if key < new_key {
+                self.push(index);
+            } else if self.g_scores[index] > self.rhs_scores[index] {
+                self.g_scores[index] = self.rhs_scores[index];
+                self.update_neighbors(grid, index);
+            } else {
+                self.g_scores[index] = INFINITY;
+                self.update_node(grid, index);
+                self.update_neighbors(grid, index);
+            }
+        }
+    }
+
+    fn update_neighbors(&mut self, grid: &Grid, index: usize) {
+        let point = self.point(index);
+        for (_, neighbor_index) in self.neighbors(point).collect::<Vec<_>>() {
+            self.update_node(grid, neighbor_index);
+        }
+    }
+
+    /// Recomputes the one-step lookahead score of a node, and queues it if it
+    /// has become inconsistent.
+    fn update_node(&mut self, grid: &Grid, index: usize) {
+        let point = self.point(index);
+
+        if point != self.goal {
+            self.rhs_scores[index] = self
+                .neighbors(point)
+                .map(|(neighbor, neighbor_index)| {
+                    self.cost(grid, point, neighbor)
+                        .saturating_add(self.g_scores[neighbor_index])
+                })
+                .min()
+                .unwrap_or(INFINITY);
+        }
+
+        self.open_keys[index] = None;
+        if self.g_scores[index] != self.rhs_scores[index] {
+            self.push(index);
+        }
+    }
+
+    fn push(&mut self, index: usize) {
+        let key = self.key(index);
+        self.open_keys[index] = Some(key);
+        self.open_nodes.push(Reverse((key, index)));
+    }
+
+    fn key(&self, index: usize) -> Key {
+        let score = self.g_scores[index].min(self.rhs_scores[index]);
+        let h = manhattan_distance(&self.start, &self.point(index)) as usize;
+
+        (
+            score.saturating_add(h).saturating_add(self.key_modifier),
+            score,
+        )
+    }
+
+    /// Cost of moving between two adjacent nodes, which is infinite if either
+    /// of them is blocked.
+    fn cost(&self, grid: &Grid, a: Point, b: Point) -> usize {
+        if grid.is_walkable(a) && grid.is_walkable(b) {
+            1
+        } else {
+            INFINITY
+        }
+    }
+
+    fn neighbors(&self, point: Point) -> impl Iterator<Item = (Point, usize)> + '_ {
+        NEIGHBORS.iter().filter_map(move |&(dx, dy)| {
+            let neighbor = Point::new(point.x + dx, point.y + dy);
+            self.index(neighbor).map(|index| (neighbor, index))
+        })
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        let in_bounds = point.x >= 0
+            && point.y >= 0
+            && (point.x as usize) < self.width
+            && (point.y as usize) < self.height;
+
+        in_bounds.then(|| point.y as usize * self.width + point.x as usize)
+    }
+
+    fn point(&self, index: usize) -> Point {
+        Point::new((index % self.width) as isize, (index / self.width) as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar;
+
+    #[test]
+    fn test_initial_path() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false],
+            vec![true,  false, true ],
+            vec![false, false, false],
+        ]);
+
+        let start = Point::new(0, 0);
+        let end = Point::new(2, 2);
+        let planner = DStarLite::new(&grid, start, end);
+
+        assert_eq!(planner.path(&grid), astar(&grid, start, end));
+    }
+
+    #[test]
+    fn test_replan() {
+        let mut grid = Grid::new(5, 5);
+        let goal = Point::new(4, 4);
+        let mut planner = DStarLite::new(&grid, Point::new(0, 0), goal);
+
+        let path = planner.path(&grid).unwrap();
+        assert_eq!(path.len(), 9);
+
+        // Walk one step, then discover a wall cutting off the whole row.
+        let walls: Vec<Point> = (0..4).map(|x| Point::new(x, 2)).collect();
+        for wall in &walls {
+            grid.set(wall.x, wall.y, true);
+        }
+        planner.update(&grid, path[1], &walls);
+
+        let path = planner.path(&grid).unwrap();
+        assert_eq!(path.first(), Some(&planner.start()));
+        assert_eq!(path.last(), Some(&goal));
+        assert_eq!(
+            Some(path.len()),
+            astar(&grid, planner.start(), goal).map(|path| path.len())
+        );
+
+        grid.set(4, 2, true);
+        planner.update(&grid, planner.start(), &[Point::new(4, 2)]);
+        assert!(planner.path(&grid).is_none());
+
+        grid.set(2, 2, false);
+        planner.update(&grid, planner.start(), &[Point::new(2, 2)]);
+        assert_eq!(
+            planner.path(&grid).map(|path| path.len()),
+            astar(&grid, planner.start(), goal).map(|path| path.len())
+        );
+    }
+}
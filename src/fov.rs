@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+
+use crate::{Grid, Point};
+
+/// Computes the points visible from `origin` within `radius`, treating blocked
+/// nodes as opaque. Blocked nodes are themselves visible, so walls bordering
+/// the visible area are included.
+///
+/// Uses symmetric shadowcasting, so `b` is visible from `a` if, and only if,
+/// `a` is visible from `b`.
+#[must_use]
+pub fn field_of_view(grid: &Grid, origin: Point, radius: usize) -> HashSet<Point> {
+    field_of_view_with(grid, grid, origin, radius)
+}
+
+/// Computes the points of `grid` visible from `origin` within `radius`, using
+/// a separate `opacity` layer where `true` marks nodes that block vision. This
+/// allows vision to differ from movement, such as windows that can be seen
+/// through but not walked through.
+///
+/// Nodes outside of `opacity` are treated as opaque.
+#[must_use]
+pub fn field_of_view_with(
+    grid: &Grid,
+    opacity: &Grid,
+    origin: Point,
+    radius: usize,
+) -> HashSet<Point> {
+    let mut visible = HashSet::new();
+
+    if grid.index(origin.x, origin.y).is_none() {
+        return visible;
+    }
+
+    visible.insert(origin);
+
+    let radius = radius as isize;
+    let is_opaque = |point: Point| opacity.get(point.x, point.y).unwrap_or(true);
+
+    for quadrant in [
+        Quadrant::North,
+        Quadrant::East,
+        Quadrant::South,
+        Quadrant::West,
+    ] {
+        let mut rows = vec![Row {
+            depth: 1,
+            start: Slope::new(-1, 1),
+            end: Slope::new(1, 1),
+        }];
+
+        while let Some(mut row) = rows.pop() {
+            if row.depth > radius {
+                continue;
+            }
+
+            let mut previous_opaque = None;
+
+            for col in row.start.round_up(row.depth)..=row.end.round_down(row.depth) {
+                let point = quadrant.transform(origin, row.depth, col);
+                let opaque = is_opaque(point);
+
+                let in_radius = row.depth * row.depth + col * col <= radius * radius;
+                let in_bounds = grid.index(point.x, point.y).is_some();
+                if in_radius && in_bounds && (opaque || row.is_symmetric(col)) {
+                    visible.insert(point);
+                }
+
+                match (previous_opaque, opaque) {
+                    (Some(true), false) => row.start = Slope::of(row.depth, col),
+                    (Some(false), true) => rows.push(Row {
+                        depth: row.depth + 1,
+                        start: row.start,
+                        end: Slope::of(row.depth, col),
+                    }),
+                    _ => {}
+                }
+
+                previous_opaque = Some(opaque);
+            }
+
+            if previous_opaque == Some(false) {
+                rows.push(Row {
+                    depth: row.depth + 1,
+                    ..row
+                });
+            }
+        }
+    }
+
+    visible
+}
+
+/// One of the four 90° cones scanned around the origin.
+#[derive(Debug, Clone, Copy)]
+enum Quadrant {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Quadrant {
+    /// Converts a position relative to this quadrant into a grid `Point`.
+    fn transform(self, origin: Point, depth: isize, col: isize) -> Point {
+        match self {
+            Self::North => Point::new(origin.x + col, origin.y - depth),
+            Self::South => Point::new(origin.x + col, origin.y + depth),
+            Self::East => Point::new(origin.x + depth, origin.y + col),
+            Self::West => Point::new(origin.x - depth, origin.y + col),
+        }
+    }
+}
+
+/// A row of nodes at a fixed distance from the origin, bounded by the slopes
+/// of the shadows cast so far.
+#[derive(Debug, Clone, Copy)]
+struct Row {
+    depth: isize,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    /// Returns whether the center of the node at `col` lies within the row's
+    /// slopes, which keeps visibility symmetric.
+    fn is_symmetric(&self, col: isize) -> bool {
+        col * self.start.den >= self.depth * self.start.num
+            && col * self.end.den <= self.depth * self.end.num
+    }
+}
+
+/// An exact rational slope, kept as a fraction to avoid rounding errors.
+#[derive(Debug, Clone, Copy)]
+struct Slope {
+    num: isize,
+    den: isize,
+}
+
+impl Slope {
+    fn new(num: isize, den: isize) -> Self {
+        Self { num, den }
+    }
+
+    /// Returns the slope to the leading edge of the node at `col`.
+    fn of(depth: isize, col: isize) -> Self {
+        Self::new(2 * col - 1, 2 * depth)
+    }
+
+    /// Returns `depth * self` rounded to the nearest integer, rounding ties
+    /// up.
+    fn round_up(self, depth: isize) -> isize {
+        (2 * depth * self.num + self.den).div_euclid(2 * self.den)
+    }
+
+    /// Returns `depth * self` rounded to the nearest integer, rounding ties
+    /// down.
+    fn round_down(self, depth: isize) -> isize {
+        -(self.den - 2 * depth * self.num).div_euclid(2 * self.den)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_room() {
+        let grid = Grid::new(7, 7);
+        let visible = field_of_view(&grid, Point::new(3, 3), 2);
+
+        assert!(visible.contains(&Point::new(3, 3)));
+        assert!(visible.contains(&Point::new(5, 3)));
+        assert!(visible.contains(&Point::new(4, 4)));
+        assert!(!visible.contains(&Point::new(5, 5)));
+        assert!(!visible.contains(&Point::new(6, 3)));
+    }
+
+    #[test]
+    fn test_walls_cast_shadows() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false, false],
+            vec![false, false, false, false, false],
+            vec![false, false, true,  false, false],
+            vec![false, false, false, false, false],
+            vec![false, false, false, false, false],
+        ]);
+
+        let origin = Point::new(2, 4);
+        let visible = field_of_view(&grid, origin, 10);
+
+        assert!(visible.contains(&Point::new(2, 2)));
+        assert!(!visible.contains(&Point::new(2, 1)));
+        assert!(!visible.contains(&Point::new(2, 0)));
+        assert!(visible.contains(&Point::new(0, 0)));
+
+        for &point in &visible {
+            assert!(
+                field_of_view(&grid, point, 10).contains(&origin),
+                "{point} is visible from {origin}, but not the reverse"
+            );
+        }
+    }
+
+    #[test]
+    fn test_opacity_layer() {
+        let grid = Grid::new(5, 1);
+        let mut opacity = Grid::new(5, 1);
+        opacity.set(2, 0, true);
+
+        let visible = field_of_view_with(&grid, &opacity, Point::new(0, 0), 10);
+
+        assert!(visible.contains(&Point::new(2, 0)));
+        assert!(!visible.contains(&Point::new(3, 0)));
+        assert_eq!(field_of_view(&grid, Point::new(0, 0), 10).len(), 5);
+    }
+}
@@ -0,0 +1,406 @@
+//! Seeded generators for procedural maps.
+//!
+//! Every generator is deterministic: the same arguments and seed always
+//! produce the same `Grid`. Walls are `true`, as everywhere else in the crate.
+//! None of the generators promise that two particular points are connected,
+//! so pass the points that must be to `connect` afterwards.
+
+use crate::{astar, Grid, Point, NEIGHBORS};
+
+/// Returns a grid where each node is a wall with probability `density`.
+#[must_use]
+pub fn noise(width: usize, height: usize, density: f64, seed: u64) -> Grid {
+    let mut rng = SplitMix64::new(seed);
+    let mut grid = Grid::new(width, height);
+
+    for (x, y) in coordinates(width, height) {
+        if rng.chance(density) {
+            grid.set(x, y, true);
+        }
+    }
+
+    grid
+}
+
+/// Returns a perfect maze, where exactly one path connects any two open
+/// nodes.
+///
+/// Passages are one node wide and lie on odd coordinates, so the maze fills
+/// the grid best when both dimensions are odd. Grids narrower or shorter than
+/// three nodes are solid walls.
+#[must_use]
+pub fn maze(width: usize, height: usize, seed: u64) -> Grid {
+    let mut rng = SplitMix64::new(seed);
+    carve_maze(width, height, &mut rng)
+}
+
+/// Returns a maze with loops, made by opening one more wall around each dead
+/// end of a perfect maze with probability `braid`.
+///
+/// A `braid` of 0 is a perfect maze, and a `braid` of 1 leaves no dead ends.
+#[must_use]
+pub fn braided_maze(width: usize, height: usize, braid: f64, seed: u64) -> Grid {
+    let mut rng = SplitMix64::new(seed);
+    let mut grid = carve_maze(width, height, &mut rng);
+
+    for (x, y) in coordinates(width, height) {
+        if x % 2 == 0 || y % 2 == 0 || grid.get(x, y) != Some(false) {
+            continue;
+        }
+
+        let mut walls = [(0, 0); 4];
+        let mut count = 0;
+        let mut passages = 0;
+
+        for (dx, dy) in NEIGHBORS {
+            if grid.get(x + dx * 2, y + dy * 2).is_none() {
+                continue;
+            }
+
+            if grid.get(x + dx, y + dy) == Some(true) {
+                walls[count] = (dx, dy);
+                count += 1;
+            } else {
+                passages += 1;
+            }
+        }
+
+        if passages == 1 && count > 0 && rng.chance(braid) {
+            let (dx, dy) = walls[rng.below(count)];
+            grid.set(x + dx, y + dy, false);
+        }
+    }
+
+    grid
+}
+
+/// Returns a cave made by filling the grid with walls at `density`, then
+/// smoothing it with `iterations` rounds of a cellular automaton.
+///
+/// Each round turns a node into a wall if at least five of the nine nodes
+/// around and including it are walls, counting nodes outside the grid as
+/// walls. Densities around 0.45 with four or five iterations give open,
+/// organic caves.
+#[must_use]
+pub fn caves(width: usize, height: usize, density: f64, iterations: usize, seed: u64) -> Grid {
+    let mut grid = noise(width, height, density, seed);
+
+    for _ in 0..iterations {
+        let mut next = Grid::new(width, height);
+
+        for (x, y) in coordinates(width, height) {
+            let walls = (-1..=1)
+                .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                .filter(|&(dx, dy)| grid.get(x + dx, y + dy) != Some(false))
+                .count();
+
+            next.set(x, y, walls >= 5);
+        }
+
+        grid = next;
+    }
+
+    grid
+}
+
+/// Returns a dungeon of up to `max_rooms` rectangular rooms joined by
+/// corridors. Every room is reachable from every other.
+///
+/// Rooms are placed at random and dropped when they would overlap, so fewer
+/// rooms may fit on small grids.
+#[must_use]
+pub fn rooms(width: usize, height: usize, max_rooms: usize, seed: u64) -> Grid {
+    const MIN_SIZE: usize = 3;
+    const MAX_SIZE: usize = 9;
+
+    let mut rng = SplitMix64::new(seed);
+    let mut grid = walls(width, height);
+    let mut placed: Vec<(usize, usize, usize, usize)> = Vec::new();
+
+    if width < MIN_SIZE + 2 || height < MIN_SIZE + 2 {
+        return grid;
+    }
+
+    for _ in 0..max_rooms * 4 {
+        if placed.len() == max_rooms {
+            break;
+        }
+
+        let room_width = rng.between(MIN_SIZE, MAX_SIZE.min(width - 2));
+        let room_height = rng.between(MIN_SIZE, MAX_SIZE.min(height - 2));
+        let x = rng.between(1, width - room_width - 1);
+        let y = rng.between(1, height - room_height - 1);
+
+        // Keep at least one wall between rooms.
+        let overlaps = placed
+            .iter()
+            .any(|&(other_x, other_y, other_width, other_height)| {
+                x <= other_x + other_width
+                    && other_x <= x + room_width
+                    && y <= other_y + other_height
+                    && other_y <= y + room_height
+            });
+
+        if overlaps {
+            continue;
+        }
+
+        for (dx, dy) in coordinates(room_width, room_height) {
+            grid.set(x as isize + dx, y as isize + dy, false);
+        }
+
+        let center = |(x, y, width, height): (usize, usize, usize, usize)| {
+            Point::new((x + width / 2) as isize, (y + height / 2) as isize)
+        };
+
+        let room = (x, y, room_width, room_height);
+        if let Some(&previous) = placed.last() {
+            carve_corridor(&mut grid, center(previous), center(room), rng.chance(0.5));
+        }
+
+        placed.push(room);
+    }
+
+    grid
+}
+
+/// Opens every point in `points` and carves corridors wherever needed so that
+/// each of them can reach the others. Points outside the grid are ignored.
+///
+/// Corridors are L-shaped and only carved between consecutive points that
+/// are not already connected, so grids that already connect the points are
+/// left untouched apart from the points themselves.
+pub fn connect(grid: &mut Grid, points: &[Point]) {
+    let points: Vec<Point> = points
+        .iter()
+        .copied()
+        .filter(|point| grid.get(point.x, point.y).is_some())
+        .collect();
+
+    for point in &points {
+        grid.set(point.x, point.y, false);
+    }
+
+    for pair in points.windows(2) {
+        if astar(grid, pair[0], pair[1]).is_none() {
+            carve_corridor(grid, pair[0], pair[1], true);
+        }
+    }
+}
+
+/// Carves a perfect maze with a randomized depth-first search over the nodes
+/// on odd coordinates.
+fn carve_maze(width: usize, height: usize, rng: &mut SplitMix64) -> Grid {
+    let mut grid = walls(width, height);
+
+    let columns = width.saturating_sub(1) / 2;
+    let rows = height.saturating_sub(1) / 2;
+    if columns == 0 || rows == 0 {
+        return grid;
+    }
+
+    let mut visited = vec![false; columns * rows];
+    let start = rng.below(columns * rows);
+    let mut stack = vec![(start % columns, start / columns)];
+    visited[start] = true;
+
+    let node = |column: usize, row: usize| ((column * 2 + 1) as isize, (row * 2 + 1) as isize);
+    let (x, y) = node(start % columns, start / columns);
+    grid.set(x, y, false);
+
+    while let Some(&(column, row)) = stack.last() {
+        let mut unvisited = [(0, 0, 0, 0); 4];
+        let mut count = 0;
+
+        for (dx, dy) in NEIGHBORS {
+            let next_column = column as isize + dx;
+            let next_row = row as isize + dy;
+
+            if next_column < 0
+                || next_row < 0
+                || next_column as usize >= columns
+                || next_row as usize >= rows
+                || visited[next_row as usize * columns + next_column as usize]
+            {
+                continue;
+            }
+
+            unvisited[count] = (next_column as usize, next_row as usize, dx, dy);
+            count += 1;
+        }
+
+        if count == 0 {
+            stack.pop();
+            continue;
+        }
+
+        let (next_column, next_row, dx, dy) = unvisited[rng.below(count)];
+        let (x, y) = node(column, row);
+        grid.set(x + dx, y + dy, false);
+        grid.set(x + dx * 2, y + dy * 2, false);
+
+        visited[next_row * columns + next_column] = true;
+        stack.push((next_column, next_row));
+    }
+
+    grid
+}
+
+/// Opens an L-shaped corridor from `from` to `to`, moving horizontally first
+/// or vertically first.
+fn carve_corridor(grid: &mut Grid, from: Point, to: Point, horizontal_first: bool) {
+    let corner = if horizontal_first {
+        Point::new(to.x, from.y)
+    } else {
+        Point::new(from.x, to.y)
+    };
+
+    for (a, b) in [(from, corner), (corner, to)] {
+        for x in a.x.min(b.x)..=a.x.max(b.x) {
+            for y in a.y.min(b.y)..=a.y.max(b.y) {
+                grid.set(x, y, false);
+            }
+        }
+    }
+}
+
+/// Returns a grid made entirely of walls.
+fn walls(width: usize, height: usize) -> Grid {
+    let mut grid = Grid::new(width, height);
+
+    for (x, y) in coordinates(width, height) {
+        grid.set(x, y, true);
+    }
+
+    grid
+}
+
+/// Iterates over every coordinate of a `width` by `height` area in row-major
+/// order.
+fn coordinates(width: usize, height: usize) -> impl Iterator<Item = (isize, isize)> {
+    (0..height as isize).flat_map(move |y| (0..width as isize).map(move |x| (x, y)))
+}
+
+/// A small, fast pseudo-random number generator, so that generators need no
+/// dependencies and give the same results on every platform.
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns a number in `low..=high`.
+    fn between(&mut self, low: usize, high: usize) -> usize {
+        low + self.below(high - low + 1)
+    }
+
+    /// Returns `true` with probability `p`.
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reachable;
+
+    fn open_points(grid: &Grid) -> Vec<Point> {
+        coordinates(grid.width(), grid.height())
+            .filter(|&(x, y)| grid.get(x, y) == Some(false))
+            .map(|(x, y)| Point::new(x, y))
+            .collect()
+    }
+
+    fn is_connected(grid: &Grid) -> bool {
+        let open = open_points(grid);
+        open.first()
+            .is_none_or(|&origin| reachable(grid, origin, usize::MAX).len() == open.len())
+    }
+
+    fn nodes(grid: &Grid) -> Vec<Option<bool>> {
+        coordinates(grid.width(), grid.height())
+            .map(|(x, y)| grid.get(x, y))
+            .collect()
+    }
+
+    #[test]
+    fn test_deterministic() {
+        assert_eq!(nodes(&noise(20, 10, 0.3, 7)), nodes(&noise(20, 10, 0.3, 7)));
+        assert_ne!(nodes(&noise(20, 10, 0.3, 7)), nodes(&noise(20, 10, 0.3, 8)));
+        assert_eq!(
+            nodes(&caves(20, 10, 0.45, 4, 7)),
+            nodes(&caves(20, 10, 0.45, 4, 7))
+        );
+        assert_eq!(nodes(&rooms(40, 30, 6, 7)), nodes(&rooms(40, 30, 6, 7)));
+    }
+
+    #[test]
+    fn test_perfect_maze() {
+        let grid = maze(21, 15, 42);
+        let open = open_points(&grid).len();
+
+        // A perfect maze is a spanning tree over its 10 x 7 cells.
+        let cells = 10 * 7;
+        assert_eq!(open, cells + cells - 1);
+        assert!(is_connected(&grid));
+    }
+
+    #[test]
+    fn test_braided_maze() {
+        let perfect = maze(21, 15, 42);
+        let braided = braided_maze(21, 15, 1.0, 42);
+
+        assert!(open_points(&braided).len() > open_points(&perfect).len());
+        assert!(is_connected(&braided));
+
+        for point in open_points(&braided) {
+            if point.x % 2 == 1 && point.y % 2 == 1 {
+                let passages = NEIGHBORS
+                    .iter()
+                    .filter(|(dx, dy)| braided.get(point.x + dx, point.y + dy) == Some(false))
+                    .count();
+                assert!(passages > 1, "Dead end at {point}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_rooms_connected() {
+        for seed in 0..10 {
+            let grid = rooms(50, 40, 8, seed);
+            assert!(!open_points(&grid).is_empty());
+            assert!(is_connected(&grid));
+        }
+    }
+
+    #[test]
+    fn test_connect() {
+        let points = [Point::new(0, 0), Point::new(29, 19), Point::new(5, 15)];
+
+        for seed in 0..10 {
+            let mut grid = caves(30, 20, 0.55, 5, seed);
+            connect(&mut grid, &points);
+
+            for pair in points.windows(2) {
+                assert!(astar(&grid, pair[0], pair[1]).is_some());
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::Point;
+use crate::{regions::Regions, Line, Map, Point};
 
 /// Represents a 2D grid that is backed by a 1D vector.
 #[derive(Debug, Clone)]
@@ -6,6 +6,7 @@ pub struct Grid {
     width: usize,
     height: usize,
     nodes: Vec<bool>,
+    regions: Option<Regions>,
 }
 
 impl Grid {
@@ -15,6 +16,7 @@ impl Grid {
             width,
             height,
             nodes: vec![false; width * height],
+            regions: None,
         }
     }
 
@@ -34,6 +36,7 @@ impl Grid {
             width,
             height,
             nodes,
+            regions: None,
         }
     }
 
@@ -67,21 +70,216 @@ impl Grid {
     }
 
     /// Returns a mutable reference to the node at (x, y) coordinates.
+    ///
+    /// Region labels cannot be kept up to date through the returned reference,
+    /// so they are discarded; see `Grid::compute_regions`.
     #[must_use]
     pub fn get_mut(&mut self, x: isize, y: isize) -> Option<&mut bool> {
-        self.index(x, y).map(|i| &mut self.nodes[i])
+        let i = self.index(x, y)?;
+        self.regions = None;
+        Some(&mut self.nodes[i])
     }
 
     /// Sets the value of the node at (x, y) coordinates.
+    ///
+    /// If region labels have been computed, they are updated in place. Only
+    /// the smaller regions are relabeled when regions merge, and only a
+    /// region that actually splits is searched.
     pub fn set(&mut self, x: isize, y: isize, value: bool) {
-        if let Some(i) = self.index(x, y) {
-            self.nodes[i] = value;
+        let Some(i) = self.index(x, y) else {
+            return;
+        };
+
+        if self.nodes[i] == value {
+            return;
+        }
+
+        self.nodes[i] = value;
+
+        if let Some(regions) = &mut self.regions {
+            regions.update(&self.nodes, self.width, i);
         }
     }
 
+    /// Returns whether a straight line between the centers of `a` and `b`
+    /// only passes through walkable nodes. Lines passing exactly through the
+    /// corner between two nodes require both of them to be walkable.
+    #[must_use]
+    pub fn line_of_sight(&self, a: Point, b: Point) -> bool {
+        Line::supercover(a, b).all(|point| self.is_walkable(point))
+    }
+
+    /// Returns whether a straight line between the centers of `a` and `b`
+    /// only passes through walkable nodes, allowing it to slip between two
+    /// blocked nodes that only touch at a corner.
+    #[must_use]
+    pub fn line_of_sight_permissive(&self, a: Point, b: Point) -> bool {
+        Line::new(a, b).all(|point| self.is_walkable(point))
+    }
+
+    /// Casts a ray from `origin` through `target`, returning the first
+    /// blocked node it touches, or `None` if the ray leaves the grid first.
+    /// Like `Grid::line_of_sight`, corners between two nodes are blocked if
+    /// either of them is.
+    #[must_use]
+    pub fn raycast(&self, origin: Point, target: Point) -> Option<Point> {
+        let dx = target.x - origin.x;
+        let dy = target.y - origin.y;
+
+        // Extends the ray far enough to leave the grid in any direction.
+        let scale = (self.width + self.height) as isize / dx.abs().max(dy.abs()).max(1) + 1;
+        let end = Point::new(origin.x + dx * scale, origin.y + dy * scale);
+
+        Line::supercover(origin, end)
+            .map_while(|point| self.get(point.x, point.y).map(|blocked| (point, blocked)))
+            .find_map(|(point, blocked)| blocked.then_some(point))
+    }
+
+    /// Labels every connected region of walkable nodes, which lets searches
+    /// reject unreachable goals without exploring the grid. The labels are
+    /// kept up to date by `Grid::set`, and discarded by `Grid::get_mut`.
+    pub fn compute_regions(&mut self) {
+        self.regions = Some(Regions::new(&self.nodes, self.width));
+    }
+
+    /// Returns the label of the region containing the node at a given
+    /// `Point`. Two points are connected by a path if, and only if, they
+    /// share a label.
+    ///
+    /// Returns `None` if the point is not walkable, or if region labels have
+    /// not been computed.
+    #[must_use]
+    pub fn region(&self, point: Point) -> Option<usize> {
+        let i = self.index(point.x, point.y)?;
+        self.regions.as_ref()?.get(i)
+    }
+
     /// Returns whether the node at a given `Point` is walkable.
     #[must_use]
     pub fn is_walkable(&self, point: Point) -> bool {
-        self.get(point.x, point.y).map_or(false, |node| !node)
+        self.get(point.x, point.y).is_some_and(|node| !node)
+    }
+}
+
+impl Map for Grid {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn is_walkable(&self, point: Point) -> bool {
+        Grid::is_walkable(self, point)
+    }
+
+    fn region(&self, point: Point) -> Option<usize> {
+        Grid::region(self, point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regions() {
+        #[rustfmt::skip]
+        let mut grid = Grid::from_2d(vec![
+            vec![false, true,  false],
+            vec![false, true,  false],
+            vec![false, false, false],
+        ]);
+
+        let left = Point::new(0, 0);
+        let right = Point::new(2, 0);
+
+        assert_eq!(grid.region(left), None);
+
+        grid.compute_regions();
+        assert_eq!(grid.region(left), grid.region(right));
+        assert_eq!(grid.region(Point::new(1, 0)), None);
+
+        grid.set(1, 2, true);
+        assert_ne!(grid.region(left), grid.region(right));
+        assert_eq!(grid.region(left), grid.region(Point::new(0, 2)));
+        assert_eq!(grid.region(right), grid.region(Point::new(2, 2)));
+
+        grid.set(1, 0, false);
+        assert_eq!(grid.region(left), grid.region(right));
+        assert_eq!(grid.region(left), grid.region(Point::new(2, 2)));
+
+        *grid.get_mut(1, 0).unwrap() = true;
+        assert_eq!(grid.region(left), None);
+    }
+
+    #[test]
+    fn test_regions_update_locally() {
+        let mut grid = Grid::new(50, 50);
+        grid.compute_regions();
+
+        let far = Point::new(49, 49);
+        let label = grid.region(far);
+
+        // Neither blocking a cell in the open nor opening it again can split
+        // or merge anything, so the label stays the same everywhere.
+        grid.set(10, 10, true);
+        assert_eq!(grid.region(far), label);
+        assert_eq!(grid.region(Point::new(10, 11)), label);
+
+        grid.set(10, 10, false);
+        assert_eq!(grid.region(far), label);
+        assert_eq!(grid.region(Point::new(10, 10)), label);
+
+        // Walling off a corner only relabels the cell that split off, and
+        // reopening the wall merges it back into the larger region.
+        grid.set(1, 0, true);
+        grid.set(0, 1, true);
+        assert_ne!(grid.region(Point::new(0, 0)), label);
+        assert_eq!(grid.region(far), label);
+
+        grid.set(1, 0, false);
+        assert_eq!(grid.region(Point::new(0, 0)), label);
+        assert_eq!(grid.region(far), label);
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, true,  false],
+            vec![false, false, false],
+            vec![true,  false, false],
+        ]);
+
+        let a = Point::new(0, 1);
+        let b = Point::new(1, 2);
+
+        assert!(grid.line_of_sight(a, Point::new(2, 1)));
+        assert!(!grid.line_of_sight(Point::new(0, 0), Point::new(2, 0)));
+        assert!(!grid.line_of_sight(a, b));
+        assert!(grid.line_of_sight_permissive(a, b));
+        assert!(!grid.line_of_sight(Point::new(2, 2), Point::new(0, 0)));
+        assert!(grid.line_of_sight_permissive(Point::new(2, 2), Point::new(0, 0)));
+    }
+
+    #[test]
+    fn test_raycast() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false],
+            vec![false, false, false, false],
+            vec![false, false, false, true ],
+        ]);
+
+        let origin = Point::new(0, 0);
+
+        assert_eq!(grid.raycast(origin, Point::new(1, 0)), None);
+        assert_eq!(
+            grid.raycast(origin, Point::new(3, 2)),
+            Some(Point::new(3, 2))
+        );
+        assert_eq!(grid.raycast(origin, Point::new(3, 1)), None);
     }
 }
@@ -0,0 +1,564 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+};
+
+use crate::{get_neighbor_points, manhattan_distance, Grid, Point};
+
+/// Runs of open border this long or longer get an entrance at both ends,
+/// rather than a single one in the middle.
+const MAX_SINGLE_ENTRANCE: usize = 6;
+
+/// A precomputed abstraction of a `Grid` for hierarchical pathfinding (HPA*).
+///
+/// The grid is split into square clusters, and the open cells along the
+/// borders between them become entrances. Distances between the entrances of
+/// each cluster are cached, so a search only needs to explore the abstract
+/// graph of entrances before refining it into a full path. Paths are close to,
+/// but not always exactly, the shortest.
+///
+/// A `Hierarchy` must only be used with the grid it was built from. After
+/// changing a node, call `Hierarchy::update` to rebuild the affected clusters.
+#[derive(Debug, Clone)]
+pub struct Hierarchy {
+    cluster_size: usize,
+    width: usize,
+    height: usize,
+    clusters_wide: usize,
+    clusters_high: usize,
+    clusters: Vec<Cluster>,
+}
+
+/// The entrances within a cluster, and the distances between them when
+/// travelling only through the cluster.
+#[derive(Debug, Clone, Default)]
+struct Cluster {
+    entrances: Vec<Point>,
+    /// Row-major matrix of distances between entrances, with `usize::MAX`
+    /// marking pairs that cannot reach each other inside the cluster.
+    distances: Vec<usize>,
+}
+
+/// A node of the abstract graph searched by `Hierarchy::find_path`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AbstractNode {
+    Start,
+    End,
+    Entrance(Point),
+}
+
+impl Hierarchy {
+    /// Builds a hierarchy over `grid` using clusters of `cluster_size` by
+    /// `cluster_size` nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cluster_size` is zero.
+    #[must_use]
+    pub fn new(grid: &Grid, cluster_size: usize) -> Self {
+        assert!(cluster_size > 0, "cluster size must be greater than zero");
+
+        let width = grid.width();
+        let height = grid.height();
+        let clusters_wide = width.div_ceil(cluster_size);
+        let clusters_high = height.div_ceil(cluster_size);
+
+        let mut hierarchy = Self {
+            cluster_size,
+            width,
+            height,
+            clusters_wide,
+            clusters_high,
+            clusters: vec![Cluster::default(); clusters_wide * clusters_high],
+        };
+
+        for cy in 0..clusters_high {
+            for cx in 0..clusters_wide {
+                hierarchy.rebuild(grid, cx, cy);
+            }
+        }
+
+        hierarchy
+    }
+
+    /// Returns the width and height of each cluster.
+    #[must_use]
+    pub fn cluster_size(&self) -> usize {
+        self.cluster_size
+    }
+
+    /// Rebuilds the clusters affected by a change to the node at `point`.
+    /// This is the cluster containing it, plus any neighboring cluster whose
+    /// shared border it lies on.
+    pub fn update(&mut self, grid: &Grid, point: Point) {
+        let Some((cx, cy)) = self.cluster_of(point) else {
+            return;
+        };
+
+        self.rebuild(grid, cx, cy);
+
+        let (min_x, min_y, max_x, max_y) = self.bounds(cx, cy);
+        let (x, y) = (point.x as usize, point.y as usize);
+
+        if x == min_x && cx > 0 {
+            self.rebuild(grid, cx - 1, cy);
+        }
+        if x + 1 == max_x && cx + 1 < self.clusters_wide {
+            self.rebuild(grid, cx + 1, cy);
+        }
+        if y == min_y && cy > 0 {
+            self.rebuild(grid, cx, cy - 1);
+        }
+        if y + 1 == max_y && cy + 1 < self.clusters_high {
+            self.rebuild(grid, cx, cy + 1);
+        }
+    }
+
+    /// Attempts to find a path from `start` to `end` through the abstract
+    /// graph, refined into a list of adjacent points like the ones returned by
+    /// `astar`. Returns `None` if no path is found.
+    #[must_use]
+    pub fn find_path(&self, grid: &Grid, start: Point, end: Point) -> Option<Vec<Point>> {
+        if !grid.is_walkable(start) || !grid.is_walkable(end) {
+            return None;
+        }
+
+        if start == end {
+            return Some(vec![start]);
+        }
+
+        let start_cluster = self.cluster_of(start)?;
+        let end_cluster = self.cluster_of(end)?;
+        let start_flood = self.flood(grid, start_cluster, start);
+        let end_flood = self.flood(grid, end_cluster, end);
+
+        let route = self.search_abstract(start, end, &start_flood, &end_flood)?;
+
+        let mut path = vec![start];
+        for pair in route.windows(2) {
+            let segment = match (pair[0], pair[1]) {
+                (AbstractNode::Start, AbstractNode::End) => start_flood.path_to(end)?,
+                (AbstractNode::Start, AbstractNode::Entrance(to)) => start_flood.path_to(to)?,
+                (AbstractNode::Entrance(from), AbstractNode::End) => {
+                    let mut segment = end_flood.path_to(from)?;
+                    segment.reverse();
+                    segment
+                }
+                (AbstractNode::Entrance(from), AbstractNode::Entrance(to)) => {
+                    if self.cluster_of(from) == self.cluster_of(to) {
+                        let cluster = self.cluster_of(from)?;
+                        self.flood(grid, cluster, from).path_to(to)?
+                    } else {
+                        vec![from, to]
+                    }
+                }
+                _ => unreachable!("abstract routes begin at the start and end at the end"),
+            };
+
+            path.extend(segment.into_iter().skip(1));
+        }
+
+        Some(path)
+    }
+
+    /// Runs A* over the abstract graph, returning the nodes along the best
+    /// route from the start to the end.
+    fn search_abstract(
+        &self,
+        start: Point,
+        end: Point,
+        start_flood: &Flood,
+        end_flood: &Flood,
+    ) -> Option<Vec<AbstractNode>> {
+        let start_cluster = self.cluster_of(start)?;
+        let end_cluster = self.cluster_of(end)?;
+
+        let mut nodes = vec![AbstractNode::Start];
+        let mut indices = HashMap::from([(AbstractNode::Start, 0)]);
+        let mut g_scores = vec![0];
+        let mut parents = vec![None];
+        let mut closed = vec![false];
+        let mut open = BinaryHeap::from([(Reverse(manhattan_distance(&start, &end)), 0)]);
+
+        let mut edges = Vec::new();
+
+        while let Some((_, index)) = open.pop() {
+            if closed[index] {
+                continue;
+            }
+            closed[index] = true;
+
+            let node = nodes[index];
+            if node == AbstractNode::End {
+                let mut route = vec![node];
+                let mut current = index;
+                while let Some(parent) = parents[current] {
+                    route.push(nodes[parent]);
+                    current = parent;
+                }
+                route.reverse();
+                return Some(route);
+            }
+
+            edges.clear();
+            match node {
+                AbstractNode::Start => {
+                    if start_cluster == end_cluster {
+                        if let Some(distance) = start_flood.distance(end) {
+                            edges.push((AbstractNode::End, distance));
+                        }
+                    }
+
+                    let cluster = &self.clusters[self.cluster_index(start_cluster)];
+                    for &entrance in &cluster.entrances {
+                        if let Some(distance) = start_flood.distance(entrance) {
+                            edges.push((AbstractNode::Entrance(entrance), distance));
+                        }
+                    }
+                }
+                AbstractNode::Entrance(point) => {
+                    self.entrance_edges(point, &mut edges);
+
+                    if self.cluster_of(point) == Some(end_cluster) {
+                        if let Some(distance) = end_flood.distance(point) {
+                            edges.push((AbstractNode::End, distance));
+                        }
+                    }
+                }
+                AbstractNode::End => unreachable!(),
+            }
+
+            for &(neighbor, cost) in &edges {
+                let tentative_g = g_scores[index] + cost;
+                let neighbor_index = *indices.entry(neighbor).or_insert_with(|| {
+                    nodes.push(neighbor);
+                    g_scores.push(usize::MAX);
+                    parents.push(None);
+                    closed.push(false);
+                    nodes.len() - 1
+                });
+
+                if closed[neighbor_index] || tentative_g >= g_scores[neighbor_index] {
+                    continue;
+                }
+
+                let h = match neighbor {
+                    AbstractNode::Entrance(point) => manhattan_distance(&point, &end),
+                    _ => 0,
+                };
+
+                g_scores[neighbor_index] = tentative_g;
+                parents[neighbor_index] = Some(index);
+                open.push((Reverse(tentative_g as isize + h), neighbor_index));
+            }
+        }
+
+        None
+    }
+
+    /// Collects the abstract edges leaving the entrance at `point`: the other
+    /// entrances of its cluster, and adjacent entrances of other clusters.
+    fn entrance_edges(&self, point: Point, edges: &mut Vec<(AbstractNode, usize)>) {
+        let Some(cluster_position) = self.cluster_of(point) else {
+            return;
+        };
+
+        let cluster = &self.clusters[self.cluster_index(cluster_position)];
+        let count = cluster.entrances.len();
+
+        if let Some(i) = cluster.entrances.iter().position(|&e| e == point) {
+            for (j, &other) in cluster.entrances.iter().enumerate() {
+                let distance = cluster.distances[i * count + j];
+                if i != j && distance != usize::MAX {
+                    edges.push((AbstractNode::Entrance(other), distance));
+                }
+            }
+        }
+
+        for (dx, dy) in crate::NEIGHBORS {
+            let neighbor = Point::new(point.x + dx, point.y + dy);
+            let Some(neighbor_position) = self.cluster_of(neighbor) else {
+                continue;
+            };
+
+            if neighbor_position != cluster_position
+                && self.clusters[self.cluster_index(neighbor_position)]
+                    .entrances
+                    .contains(&neighbor)
+            {
+                edges.push((AbstractNode::Entrance(neighbor), 1));
+            }
+        }
+    }
+
+    /// Recomputes the entrances and cached distances of a cluster.
+    fn rebuild(&mut self, grid: &Grid, cx: usize, cy: usize) {
+        let (min_x, min_y, max_x, max_y) = self.bounds(cx, cy);
+        let mut entrances = Vec::new();
+
+        // Each border is walked in the same order from both sides, so the two
+        // clusters always agree on where the entrances are.
+        if cx > 0 {
+            let pairs = (min_y..max_y).map(|y| (Point::new(min_x as isize, y as isize), -1, 0));
+            add_entrances(grid, pairs, &mut entrances);
+        }
+        if cx + 1 < self.clusters_wide {
+            let pairs = (min_y..max_y).map(|y| (Point::new(max_x as isize - 1, y as isize), 1, 0));
+            add_entrances(grid, pairs, &mut entrances);
+        }
+        if cy > 0 {
+            let pairs = (min_x..max_x).map(|x| (Point::new(x as isize, min_y as isize), 0, -1));
+            add_entrances(grid, pairs, &mut entrances);
+        }
+        if cy + 1 < self.clusters_high {
+            let pairs = (min_x..max_x).map(|x| (Point::new(x as isize, max_y as isize - 1), 0, 1));
+            add_entrances(grid, pairs, &mut entrances);
+        }
+
+        let count = entrances.len();
+        let mut distances = vec![usize::MAX; count * count];
+
+        for (i, &entrance) in entrances.iter().enumerate() {
+            let flood = self.flood(grid, (cx, cy), entrance);
+            for (j, &other) in entrances.iter().enumerate() {
+                if let Some(distance) = flood.distance(other) {
+                    distances[i * count + j] = distance;
+                }
+            }
+        }
+
+        let index = self.cluster_index((cx, cy));
+        self.clusters[index] = Cluster {
+            entrances,
+            distances,
+        };
+    }
+
+    /// Runs a breadth-first search from `source` that never leaves the given
+    /// cluster.
+    fn flood(&self, grid: &Grid, (cx, cy): (usize, usize), source: Point) -> Flood {
+        let (min_x, min_y, max_x, max_y) = self.bounds(cx, cy);
+        let mut flood = Flood {
+            min_x: min_x as isize,
+            min_y: min_y as isize,
+            width: max_x - min_x,
+            height: max_y - min_y,
+            distances: vec![usize::MAX; (max_x - min_x) * (max_y - min_y)],
+            parents: vec![usize::MAX; (max_x - min_x) * (max_y - min_y)],
+        };
+
+        let Some(source_index) = flood.index(source) else {
+            return flood;
+        };
+
+        flood.distances[source_index] = 0;
+        let mut queue = VecDeque::from([source]);
+
+        while let Some(point) = queue.pop_front() {
+            let index = flood.index(point).unwrap_or_default();
+            for neighbor in get_neighbor_points(grid, point) {
+                let Some(neighbor_index) = flood.index(neighbor) else {
+                    continue;
+                };
+
+                if flood.distances[neighbor_index] == usize::MAX {
+                    flood.distances[neighbor_index] = flood.distances[index] + 1;
+                    flood.parents[neighbor_index] = index;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        flood
+    }
+
+    /// Returns the bounds of a cluster as `(min_x, min_y, max_x, max_y)`, with
+    /// exclusive maximums. Clusters along the right and bottom edges of the
+    /// grid may be smaller than the cluster size.
+    fn bounds(&self, cx: usize, cy: usize) -> (usize, usize, usize, usize) {
+        let min_x = cx * self.cluster_size;
+        let min_y = cy * self.cluster_size;
+
+        (
+            min_x,
+            min_y,
+            (min_x + self.cluster_size).min(self.width),
+            (min_y + self.cluster_size).min(self.height),
+        )
+    }
+
+    /// Returns the position of the cluster containing `point`.
+    fn cluster_of(&self, point: Point) -> Option<(usize, usize)> {
+        let in_bounds = point.x >= 0
+            && point.y >= 0
+            && (point.x as usize) < self.width
+            && (point.y as usize) < self.height;
+
+        in_bounds.then(|| {
+            (
+                point.x as usize / self.cluster_size,
+                point.y as usize / self.cluster_size,
+            )
+        })
+    }
+
+    fn cluster_index(&self, (cx, cy): (usize, usize)) -> usize {
+        cy * self.clusters_wide + cx
+    }
+}
+
+/// Adds entrances along one border of a cluster. Each item is a node on the
+/// inside of the border and the direction of the node facing it across.
+fn add_entrances(
+    grid: &Grid,
+    border: impl Iterator<Item = (Point, isize, isize)>,
+    entrances: &mut Vec<Point>,
+) {
+    let mut run = Vec::new();
+    let mut add_run = |run: &mut Vec<Point>| {
+        let chosen: &[Point] = match run.len() {
+            0 => &[],
+            len if len >= MAX_SINGLE_ENTRANCE => &[run[0], run[len - 1]],
+            len => &[run[len / 2]],
+        };
+
+        for &point in chosen {
+            if !entrances.contains(&point) {
+                entrances.push(point);
+            }
+        }
+
+        run.clear();
+    };
+
+    for (point, dx, dy) in border {
+        if grid.is_walkable(point) && grid.is_walkable(Point::new(point.x + dx, point.y + dy)) {
+            run.push(point);
+        } else {
+            add_run(&mut run);
+        }
+    }
+
+    add_run(&mut run);
+}
+
+/// The result of a breadth-first search confined to a single cluster.
+struct Flood {
+    min_x: isize,
+    min_y: isize,
+    width: usize,
+    height: usize,
+    distances: Vec<usize>,
+    parents: Vec<usize>,
+}
+
+impl Flood {
+    fn index(&self, point: Point) -> Option<usize> {
+        let x = point.x - self.min_x;
+        let y = point.y - self.min_y;
+        let in_bounds = x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height;
+
+        in_bounds.then(|| y as usize * self.width + x as usize)
+    }
+
+    fn distance(&self, point: Point) -> Option<usize> {
+        self.index(point)
+            .map(|i| self.distances[i])
+            .filter(|&distance| distance != usize::MAX)
+    }
+
+    /// Returns the path from the source of the flood to `point`.
+    fn path_to(&self, point: Point) -> Option<Vec<Point>> {
+        let mut index = self.index(point)?;
+        let mut path = Vec::with_capacity(self.distance(point)? + 1);
+
+        loop {
+            path.push(Point::new(
+                self.min_x + (index % self.width) as isize,
+                self.min_y + (index / self.width) as isize,
+            ));
+
+            if self.distances[index] == 0 {
+                break;
+            }
+            index = self.parents[index];
+        }
+
+        path.reverse();
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar;
+
+    fn assert_valid(grid: &Grid, path: &[Point], start: Point, end: Point) {
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&end));
+
+        for window in path.windows(2) {
+            assert_eq!(manhattan_distance(&window[0], &window[1]), 1);
+        }
+
+        for point in path {
+            assert!(
+                grid.is_walkable(*point),
+                "Path contains collision at {point}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_path() {
+        let mut grid = Grid::new(12, 12);
+        for y in 0..11 {
+            grid.set(5, y, true);
+        }
+
+        let hierarchy = Hierarchy::new(&grid, 4);
+        let start = Point::new(0, 0);
+        let end = Point::new(11, 0);
+
+        let path = hierarchy.find_path(&grid, start, end).unwrap();
+        let optimal = astar(&grid, start, end).unwrap();
+
+        assert_valid(&grid, &path, start, end);
+        assert!(path.len() <= optimal.len() + 4);
+    }
+
+    #[test]
+    fn test_same_cluster() {
+        let grid = Grid::new(8, 8);
+        let hierarchy = Hierarchy::new(&grid, 4);
+        let start = Point::new(1, 1);
+        let end = Point::new(2, 3);
+
+        let path = hierarchy.find_path(&grid, start, end).unwrap();
+
+        assert_valid(&grid, &path, start, end);
+        assert_eq!(path.len(), 4);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut grid = Grid::new(8, 4);
+        let mut hierarchy = Hierarchy::new(&grid, 4);
+        let start = Point::new(0, 0);
+        let end = Point::new(7, 3);
+
+        for y in 0..4 {
+            grid.set(4, y, true);
+            hierarchy.update(&grid, Point::new(4, y));
+        }
+
+        assert!(hierarchy.find_path(&grid, start, end).is_none());
+
+        grid.set(4, 2, false);
+        hierarchy.update(&grid, Point::new(4, 2));
+
+        let path = hierarchy.find_path(&grid, start, end).unwrap();
+        assert_valid(&grid, &path, start, end);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{manhattan_distance, Grid, Point, NEIGHBORS};
+
+/// Attempts to find the shortest path from `start` to `end` using the
+/// Iterative Deepening A* (IDA*) algorithm. Returns `None` if no path is
+/// found.
+///
+/// Unlike `astar`, which allocates bookkeeping for every node of the grid,
+/// this only stores the path currently being explored, so memory grows with
+/// the length of the path rather than the size of the grid. The trade-off is
+/// time: nodes are revisited many times, so searches are much slower,
+/// especially when `end` is unreachable. Computing region labels with
+/// `Grid::compute_regions` lets unreachable goals be rejected immediately.
+#[must_use]
+pub fn ida_star(grid: &Grid, start: Point, end: Point) -> Option<Vec<Point>> {
+    if let (Some(a), Some(b)) = (grid.region(start), grid.region(end)) {
+        if a != b {
+            return None;
+        }
+    }
+
+    let mut threshold = manhattan_distance(&start, &end);
+
+    let mut path = Vec::new();
+    // The index of the next neighbor to try at each depth of `path`.
+    let mut cursors = Vec::new();
+
+    loop {
+        path.clear();
+        path.push(start);
+        cursors.clear();
+        cursors.push(0);
+
+        let mut next_threshold = isize::MAX;
+
+        while let Some(cursor) = cursors.last_mut() {
+            let current = path[path.len() - 1];
+
+            if current == end {
+                return Some(path);
+            }
+
+            let Some(&(dx, dy)) = NEIGHBORS.get(*cursor) else {
+                cursors.pop();
+                path.pop();
+                continue;
+            };
+            *cursor += 1;
+
+            let neighbor = Point::new(current.x + dx, current.y + dy);
+            if !grid.is_walkable(neighbor) || path.contains(&neighbor) {
+                continue;
+            }
+
+            let f = path.len() as isize + manhattan_distance(&neighbor, &end);
+            if f > threshold {
+                next_threshold = next_threshold.min(f);
+                continue;
+            }
+
+            path.push(neighbor);
+            cursors.push(0);
+        }
+
+        if next_threshold == isize::MAX {
+            return None;
+        }
+
+        threshold = next_threshold;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar;
+
+    #[test]
+    fn test_matches_astar() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false, false],
+            vec![true,  true,  true,  false, false],
+            vec![false, false, false, false, false],
+            vec![false, true,  true,  true,  true ],
+            vec![false, false, false, false, false],
+        ]);
+
+        let start = Point::new(0, 0);
+        let end = Point::new(4, 4);
+
+        let path = ida_star(&grid, start, end).unwrap();
+
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&end));
+        assert_eq!(path.len(), astar(&grid, start, end).unwrap().len());
+    }
+
+    #[test]
+    fn test_no_path() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false],
+            vec![false, true,  true ],
+            vec![false, true,  false],
+        ]);
+
+        assert!(ida_star(&grid, Point::new(0, 0), Point::new(2, 2)).is_none());
+    }
+}
@@ -1,83 +1,356 @@
 #![doc = include_str!("../README.md")]
+mod alternatives;
+mod ara;
+mod cache;
+mod cbs;
+mod chunked;
+mod clearance;
+mod cooperative;
+mod database;
+mod dstar;
+mod fov;
+pub mod generators;
 mod grid;
+mod hierarchy;
+mod ida;
+mod line;
+mod map;
+mod matrix;
 mod node;
+mod path;
 mod point;
+mod reachable;
+mod regions;
+mod route;
+mod smooth;
+mod stepped;
+mod theta;
 
 use std::collections::BinaryHeap;
 
+pub use alternatives::{alternative_paths, k_shortest_paths};
+pub use ara::{AnytimePath, AraStar};
+pub use cache::PathCache;
+pub use cbs::conflict_based_search;
+pub use chunked::{ChunkedGrid, ChunkedView};
+pub use clearance::{astar_sized, Clearance};
+pub use cooperative::{
+    avoid_moving_obstacles, cooperative_paths, space_time_astar, ReservationTable,
+};
+pub use database::PathDatabase;
+pub use dstar::DStarLite;
+pub use fov::{field_of_view, field_of_view_with};
 pub use grid::Grid;
+pub use hierarchy::Hierarchy;
+pub use ida::ida_star;
+pub use line::Line;
+pub use map::Map;
+pub use matrix::{distance_matrix, distance_matrix_parallel, DistanceMatrix};
 pub use node::Node;
+pub use path::PathResult;
 pub use point::Point;
+pub use reachable::{reachable, Reachable};
+pub use route::{route, Route, VisitOrder};
+pub use smooth::smooth_path;
+pub use stepped::{SearchStatus, SteppedSearch};
+pub use theta::{lazy_theta_star, theta_star};
+
+use crate::map::{map_index, map_region};
 
 /// Attempts to find the shortest path from `start` to `end` using the A*
 /// algorithm. Returns `None` if no path is found.
+///
+/// Any `Map` can be searched, and paths minimize the total `Map::cost` of the
+/// nodes entered. If the map reports regions, an `end` in a different region
+/// than `start` is rejected without searching; see `Grid::compute_regions`.
 #[must_use]
-pub fn astar(grid: &Grid, start: Point, end: Point) -> Option<Vec<Point>> {
-    let width = grid.width();
-    let height = grid.height();
-    let capacity = width * height;
-
-    let mut open_nodes = BinaryHeap::new(); // Min-heap; see `Node` impl
-    let mut closed_nodes = vec![false; capacity];
-    let mut g_scores = vec![u16::MAX; capacity];
-    let mut all_nodes = Vec::with_capacity(capacity);
-
-    let start_node = Node {
-        point: start,
-        g: 0,
-        h: manhattan_distance(&start, &end),
-        parent_index: None,
-    };
+pub fn astar<M: Map + ?Sized>(grid: &M, start: Point, end: Point) -> Option<Vec<Point>> {
+    if let (Some(a), Some(b)) = (map_region(grid, start), map_region(grid, end)) {
+        if a != b {
+            return None;
+        }
+    }
 
-    let start_index = point_to_index(start, width);
-    g_scores[start_index] = 0;
-    all_nodes.push(start_node);
-    open_nodes.push((0, 0));
+    let result = search(
+        grid,
+        start,
+        |point| manhattan_distance(&point, &end),
+        |point| point == end,
+    );
 
-    while let Some((_f_score, current_index)) = open_nodes.pop() {
-        let current = all_nodes[current_index];
+    Some(retrace_path(&result.nodes, result.found?))
+}
 
-        if current.point == end {
-            return Some(retrace_path(&all_nodes, current_index));
-        }
+/// Attempts to find the shortest path from `start` to `end` using the A*
+/// algorithm. If `end` is unreachable, falls back to a path towards the
+/// explored point closest to `end`, preferring the cheapest one when several
+/// are equally close.
+///
+/// The fallback is returned as `PathResult::Partial`, so callers can still
+/// move towards an unreachable target while knowing they will not arrive.
+#[must_use]
+pub fn astar_partial<M: Map + ?Sized>(grid: &M, start: Point, end: Point) -> PathResult {
+    let result = search(
+        grid,
+        start,
+        |point| manhattan_distance(&point, &end),
+        |point| point == end,
+    );
+
+    match result.found {
+        Some(index) => PathResult::Complete(retrace_path(&result.nodes, index)),
+        None => PathResult::Partial(retrace_path(&result.nodes, result.closest)),
+    }
+}
 
-        let current_point_index = point_to_index(current.point, width);
-        if closed_nodes[current_point_index] {
-            continue;
+/// Attempts to find the shortest path from `start` to whichever of `goals` is
+/// closest. Returns the index of the goal that was reached alongside the path,
+/// or `None` if none of the goals are reachable.
+///
+/// This is a single search guided by the smallest Manhattan distance to any
+/// goal, so it is considerably cheaper than calling `astar` once per goal.
+/// If the map reports regions, goals in a different region than `start` are
+/// ignored.
+#[must_use]
+pub fn astar_nearest<M: Map + ?Sized>(
+    grid: &M,
+    start: Point,
+    goals: &[Point],
+) -> Option<(usize, Vec<Point>)> {
+    let start_region = map_region(grid, start);
+    let goals: Vec<(usize, Point)> = goals
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(_, goal)| match (start_region, map_region(grid, goal)) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        })
+        .collect();
+
+    if goals.is_empty() {
+        return None;
+    }
+
+    // Maps each cell to the first goal occupying it, so duplicate goals report
+    // the lowest index.
+    let mut goal_indices = vec![None; grid.width() * grid.height()];
+    for &(i, goal) in &goals {
+        if let Some(index) = map_index(grid, goal) {
+            goal_indices[index].get_or_insert(i);
         }
-        closed_nodes[current_point_index] = true;
+    }
+
+    let result = search(
+        grid,
+        start,
+        |point| {
+            goals
+                .iter()
+                .map(|(_, goal)| manhattan_distance(&point, goal))
+                .min()
+                .unwrap_or(0)
+        },
+        |point| map_index(grid, point).is_some_and(|index| goal_indices[index].is_some()),
+    );
+
+    let end_index = result.found?;
+    let end = result.nodes[end_index].point;
+    let goal = goal_indices[map_index(grid, end)?]?;
+
+    Some((goal, retrace_path(&result.nodes, end_index)))
+}
 
-        let current_g = current.g;
+/// The nodes created by `search`, with indices that can be passed to
+/// `retrace_path`.
+struct SearchResult {
+    nodes: Vec<Node>,
+    /// Index of the goal node, if one was reached.
+    found: Option<usize>,
+    /// Index of the closed node with the lowest `h` cost, ties broken by the
+    /// lowest `g` cost.
+    closest: usize,
+}
 
-        for neighbor_point in get_neighbor_points(grid, current.point) {
-            let neighbor_index = point_to_index(neighbor_point, width);
-            if closed_nodes[neighbor_index] {
-                continue;
-            }
+/// Runs A* from `start` until a point satisfying `is_goal` is popped from the
+/// open set, or every reachable point has been explored.
+///
+/// `heuristic` must never overestimate the distance to the nearest goal, or
+/// the returned path may not be the shortest.
+fn search<M: Map + ?Sized>(
+    grid: &M,
+    start: Point,
+    heuristic: impl Fn(Point) -> isize,
+    is_goal: impl Fn(Point) -> bool,
+) -> SearchResult {
+    search_filtered(grid, start, heuristic, is_goal, |_| true)
+}
 
-            let tentative_g = current_g + 1;
+/// Like `search`, but only steps onto walkable points for which `is_passable`
+/// also returns `true`.
+fn search_filtered<M: Map + ?Sized>(
+    grid: &M,
+    start: Point,
+    heuristic: impl Fn(Point) -> isize,
+    is_goal: impl Fn(Point) -> bool,
+    is_passable: impl Fn(Point) -> bool,
+) -> SearchResult {
+    if !grid.contains(start) {
+        return SearchResult {
+            nodes: vec![Node::from(start)],
+            found: None,
+            closest: 0,
+        };
+    }
+
+    let mut state = SearchState::new(grid, start, heuristic(start));
+    let found = match state.step(grid, usize::MAX, heuristic, is_goal, is_passable) {
+        Step::Found(index) => Some(index),
+        Step::Exhausted | Step::Paused => None,
+    };
+
+    SearchResult {
+        nodes: state.nodes,
+        found,
+        closest: state.closest,
+    }
+}
+
+/// The outcome of advancing a `SearchState`.
+enum Step {
+    /// A goal was reached; holds the index of its node.
+    Found(usize),
+    /// Every reachable point was explored without reaching a goal.
+    Exhausted,
+    /// The expansion budget ran out before the search finished.
+    Paused,
+}
+
+/// The open and closed sets of an A* search, kept between calls to
+/// `SearchState::step` so a search can be spread over several calls.
+#[derive(Debug, Clone)]
+struct SearchState {
+    origin: Point,
+    width: usize,
+    open_nodes: BinaryHeap<(isize, usize)>,
+    closed_nodes: Vec<bool>,
+    g_scores: Vec<isize>,
+    nodes: Vec<Node>,
+    /// Index of the closed node with the lowest `h` cost, ties broken by the
+    /// lowest `g` cost.
+    closest: usize,
+}
 
-            if tentative_g >= g_scores[neighbor_index] as isize {
+impl SearchState {
+    fn new<M: Map + ?Sized>(grid: &M, start: Point, h: isize) -> Self {
+        let width = grid.width();
+        let height = grid.height();
+        let capacity = width * height;
+
+        let mut state = Self {
+            origin: grid.origin(),
+            width,
+            open_nodes: BinaryHeap::new(), // Max-heap of negated f-costs
+            closed_nodes: vec![false; capacity],
+            g_scores: vec![isize::MAX; capacity],
+            nodes: Vec::with_capacity(capacity),
+            closest: 0,
+        };
+
+        let start_node = Node {
+            point: start,
+            g: 0,
+            h,
+            parent_index: None,
+        };
+
+        let start_index = state.index(start);
+        state.g_scores[start_index] = 0;
+        state.nodes.push(start_node);
+        state.open_nodes.push((0, 0));
+
+        state
+    }
+
+    /// Expands up to `budget` nodes, stopping early if a point satisfying
+    /// `is_goal` is popped from the open set or the open set runs dry. Only
+    /// walkable points for which `is_passable` returns `true` are entered.
+    fn step<M: Map + ?Sized>(
+        &mut self,
+        grid: &M,
+        budget: usize,
+        heuristic: impl Fn(Point) -> isize,
+        is_goal: impl Fn(Point) -> bool,
+        is_passable: impl Fn(Point) -> bool,
+    ) -> Step {
+        let mut expanded = 0;
+
+        while expanded < budget {
+            let Some((_f_score, current_index)) = self.open_nodes.pop() else {
+                return Step::Exhausted;
+            };
+
+            let current = self.nodes[current_index];
+
+            if is_goal(current.point) {
+                self.closest = current_index;
+                return Step::Found(current_index);
+            }
+
+            let current_point_index = self.index(current.point);
+            if self.closed_nodes[current_point_index] {
                 continue;
             }
+            self.closed_nodes[current_point_index] = true;
+            expanded += 1;
 
-            let h = manhattan_distance(&neighbor_point, &end);
-            let f = tentative_g + h;
+            let best = &self.nodes[self.closest];
+            if (current.h, current.g) < (best.h, best.g) {
+                self.closest = current_index;
+            }
 
-            let neighbor = Node {
-                point: neighbor_point,
-                g: tentative_g,
-                h,
-                parent_index: Some(current_index),
-            };
+            let current_g = current.g;
+
+            for neighbor_point in get_neighbor_points(grid, current.point) {
+                let neighbor_index = self.index(neighbor_point);
+                if self.closed_nodes[neighbor_index] || !is_passable(neighbor_point) {
+                    continue;
+                }
 
-            g_scores[neighbor_index] = tentative_g as u16;
-            all_nodes.push(neighbor);
-            open_nodes.push((-f, all_nodes.len() - 1));
+                // Saturates, so the largest costs leave a node unreachable
+                // rather than wrapping around to cheap ones.
+                let cost = isize::try_from(grid.cost(neighbor_point)).unwrap_or(isize::MAX);
+                let tentative_g = current_g.saturating_add(cost);
+
+                if tentative_g >= self.g_scores[neighbor_index] {
+                    continue;
+                }
+
+                let h = heuristic(neighbor_point);
+                let f = tentative_g.saturating_add(h);
+
+                let neighbor = Node {
+                    point: neighbor_point,
+                    g: tentative_g,
+                    h,
+                    parent_index: Some(current_index),
+                };
+
+                self.g_scores[neighbor_index] = tentative_g;
+                self.nodes.push(neighbor);
+                self.open_nodes.push((-f, self.nodes.len() - 1));
+            }
         }
+
+        Step::Paused
     }
 
-    None
+    /// Converts a point within the searched map to an index into the closed
+    /// set and g-scores.
+    #[inline]
+    fn index(&self, point: Point) -> usize {
+        (point.y - self.origin.y) as usize * self.width + (point.x - self.origin.x) as usize
+    }
 }
 
 /// Converts a `Point` to an index in a 1D vector.
@@ -117,13 +390,14 @@ fn manhattan_distance(a: &Point, b: &Point) -> isize {
 // TODO: Support diagonal movement?
 const NEIGHBORS: [(isize, isize); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
 
-fn get_neighbor_points(grid: &Grid, point: Point) -> impl Iterator<Item = Point> + '_ {
+/// Returns the walkable points adjacent to `point` that lie within the map.
+fn get_neighbor_points<M: Map + ?Sized>(
+    grid: &M,
+    point: Point,
+) -> impl Iterator<Item = Point> + '_ {
     NEIGHBORS.iter().filter_map(move |&(dx, dy)| {
-        let new_x = point.x + dx;
-        let new_y = point.y + dy;
-        grid.get(new_x, new_y)
-            .filter(|&node| !node)
-            .map(|_| Point::new(new_x, new_y))
+        let neighbor = Point::new(point.x + dx, point.y + dy);
+        (grid.contains(neighbor) && grid.is_walkable(neighbor)).then_some(neighbor)
     })
 }
 
@@ -244,4 +518,244 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_nearest_goal() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false, false],
+            vec![false, true,  true,  true,  false],
+            vec![false, false, false, true,  false],
+        ]);
+
+        let start = Point::new(2, 2);
+        let goals = [Point::new(4, 2), Point::new(0, 0), Point::new(4, 0)];
+
+        let (goal, path) = astar_nearest(&grid, start, &goals).unwrap();
+
+        assert_eq!(goal, 1);
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&goals[1]));
+        assert_eq!(path.len(), 5);
+    }
+
+    #[test]
+    fn test_nearest_goal_unreachable() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, true,  false],
+            vec![true,  true,  false],
+            vec![false, false, false],
+        ]);
+
+        assert!(astar_nearest(&grid, Point::new(0, 0), &[Point::new(2, 2)]).is_none());
+        assert!(astar_nearest(&grid, Point::new(2, 2), &[]).is_none());
+    }
+
+    #[test]
+    fn test_partial_path() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false],
+            vec![false, false, true,  true ],
+            vec![false, false, true,  false],
+        ]);
+
+        let start = Point::new(0, 2);
+        let end = Point::new(3, 2);
+
+        let result = astar_partial(&grid, start, end);
+
+        assert!(result.is_partial());
+        assert_eq!(result.path().first(), Some(&start));
+        assert_eq!(result.path().last(), Some(&Point::new(1, 2)));
+
+        let result = astar_partial(&grid, start, Point::new(3, 0));
+
+        assert!(!result.is_partial());
+        assert_eq!(result.path().len(), 6);
+    }
+
+    #[test]
+    fn test_regions_reject_unreachable() {
+        #[rustfmt::skip]
+        let mut grid = Grid::from_2d(vec![
+            vec![false, true,  false],
+            vec![false, true,  false],
+            vec![false, true,  false],
+        ]);
+        grid.compute_regions();
+
+        let start = Point::new(0, 0);
+        let goals = [Point::new(2, 2), Point::new(0, 2)];
+
+        assert!(astar(&grid, start, goals[0]).is_none());
+        assert_eq!(astar_nearest(&grid, start, &goals).map(|(i, _)| i), Some(1));
+
+        grid.set(1, 1, false);
+        assert_eq!(
+            astar(&grid, start, goals[0]).map(|path| path.len()),
+            Some(5)
+        );
+    }
+
+    /// A map with a swamp that is expensive to cross, offset so that it
+    /// covers negative coordinates.
+    struct Swamp;
+
+    impl Map for Swamp {
+        fn width(&self) -> usize {
+            5
+        }
+
+        fn height(&self) -> usize {
+            3
+        }
+
+        fn origin(&self) -> Point {
+            Point::new(-2, -1)
+        }
+
+        fn is_walkable(&self, _point: Point) -> bool {
+            true
+        }
+
+        fn cost(&self, point: Point) -> usize {
+            if point.y == 0 && point.x.abs() < 2 {
+                10
+            } else {
+                1
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_map() {
+        let start = Point::new(-2, 0);
+        let end = Point::new(2, 0);
+
+        // Detouring around the swamp costs 6, crossing it costs 31.
+        let path = astar(&Swamp, start, end).unwrap();
+        assert_eq!(path.len(), 7);
+        assert!(path.iter().all(|point| point.y != 0 || point.x.abs() == 2));
+
+        let map: &dyn Map = &Swamp;
+        assert_eq!(astar(map, start, end), Some(path));
+        assert_eq!(astar(map, start, Point::new(3, 0)), None);
+        assert_eq!(astar(map, Point::new(-3, 0), end), None);
+    }
+
+    /// A map that labels every node with its own region, reading the labels
+    /// from storage that only covers the map.
+    struct Tiles {
+        regions: Vec<usize>,
+    }
+
+    impl Map for Tiles {
+        fn width(&self) -> usize {
+            3
+        }
+
+        fn height(&self) -> usize {
+            1
+        }
+
+        fn is_walkable(&self, _point: Point) -> bool {
+            true
+        }
+
+        fn region(&self, point: Point) -> Option<usize> {
+            Some(self.regions[point.x as usize])
+        }
+    }
+
+    #[test]
+    fn test_regions_outside_map() {
+        let tiles = Tiles {
+            regions: vec![0, 0, 0],
+        };
+
+        assert_eq!(astar(&tiles, Point::new(0, 0), Point::new(5, 5)), None);
+        assert_eq!(
+            astar_nearest(
+                &tiles,
+                Point::new(0, 0),
+                &[Point::new(5, 0), Point::new(2, 0)]
+            )
+            .map(|(goal, _)| goal),
+            Some(1)
+        );
+    }
+
+    /// A map whose bounds leave out one node in the middle of its only row.
+    struct Gap;
+
+    impl Map for Gap {
+        fn width(&self) -> usize {
+            5
+        }
+
+        fn height(&self) -> usize {
+            2
+        }
+
+        fn is_walkable(&self, _point: Point) -> bool {
+            true
+        }
+
+        fn contains(&self, point: Point) -> bool {
+            map_index(self, point).is_some() && point != Point::new(2, 0)
+        }
+    }
+
+    #[test]
+    fn test_reference_map_forwards_contains() {
+        let start = Point::new(0, 0);
+        let end = Point::new(4, 0);
+
+        let path = astar(&Gap, start, end).unwrap();
+        assert_eq!(astar(&&Gap, start, end), Some(path.clone()));
+        assert_eq!(path.len(), 7);
+        assert!(!path.contains(&Point::new(2, 0)));
+    }
+
+    /// A corridor two nodes high whose middle node in the top row costs as
+    /// much as `Map::cost` allows, optionally with the bottom row blocked.
+    struct Toll {
+        detour: bool,
+    }
+
+    impl Map for Toll {
+        fn width(&self) -> usize {
+            5
+        }
+
+        fn height(&self) -> usize {
+            2
+        }
+
+        fn is_walkable(&self, point: Point) -> bool {
+            self.detour || point.y == 0
+        }
+
+        fn cost(&self, point: Point) -> usize {
+            if point == Point::new(2, 0) {
+                usize::MAX
+            } else {
+                1
+            }
+        }
+    }
+
+    #[test]
+    fn test_huge_cost() {
+        let start = Point::new(0, 0);
+        let end = Point::new(4, 0);
+
+        let path = astar(&Toll { detour: true }, start, end).unwrap();
+        assert_eq!(path.len(), 7);
+        assert!(!path.contains(&Point::new(2, 0)));
+
+        assert_eq!(astar(&Toll { detour: false }, start, end), None);
+    }
 }
@@ -0,0 +1,148 @@
+use crate::Point;
+
+/// An iterator over the nodes a straight line passes through, from the center
+/// of one `Point` to the center of another, inclusive of both.
+///
+/// Lines can pass exactly through the corner between four nodes. `Line::new`
+/// skips the two nodes that are only touched at that corner, whereas
+/// `Line::supercover` yields them before the node diagonally across.
+#[derive(Debug, Clone)]
+pub struct Line {
+    x: isize,
+    y: isize,
+    dx: isize,
+    dy: isize,
+    step_x: isize,
+    step_y: isize,
+    ix: isize,
+    iy: isize,
+    supercover: bool,
+    started: bool,
+    pending: [Option<Point>; 2],
+}
+
+impl Line {
+    /// Creates a line that only yields nodes whose interior it crosses.
+    #[must_use]
+    pub fn new(from: Point, to: Point) -> Self {
+        Self {
+            x: from.x,
+            y: from.y,
+            dx: (to.x - from.x).abs(),
+            dy: (to.y - from.y).abs(),
+            step_x: (to.x - from.x).signum(),
+            step_y: (to.y - from.y).signum(),
+            ix: 0,
+            iy: 0,
+            supercover: false,
+            started: false,
+            pending: [None; 2],
+        }
+    }
+
+    /// Creates a line that yields every node it touches, including both nodes
+    /// beside any corner it passes exactly through.
+    #[must_use]
+    pub fn supercover(from: Point, to: Point) -> Self {
+        Self {
+            supercover: true,
+            ..Self::new(from, to)
+        }
+    }
+}
+
+impl Iterator for Line {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        if let Some(point) = self.pending.iter_mut().find_map(Option::take) {
+            return Some(point);
+        }
+
+        if !self.started {
+            self.started = true;
+            return Some(Point::new(self.x, self.y));
+        }
+
+        if self.ix >= self.dx && self.iy >= self.dy {
+            return None;
+        }
+
+        // Compares where the line crosses the next vertical and horizontal
+        // grid lines, scaled to stay in integers.
+        let decision = (1 + 2 * self.ix) * self.dy - (1 + 2 * self.iy) * self.dx;
+
+        if decision == 0 {
+            let beside_x = Point::new(self.x + self.step_x, self.y);
+            let beside_y = Point::new(self.x, self.y + self.step_y);
+
+            self.x += self.step_x;
+            self.y += self.step_y;
+            self.ix += 1;
+            self.iy += 1;
+
+            if self.supercover {
+                self.pending = [Some(beside_y), Some(Point::new(self.x, self.y))];
+                return Some(beside_x);
+            }
+        } else if decision < 0 {
+            self.x += self.step_x;
+            self.ix += 1;
+        } else {
+            self.y += self.step_y;
+            self.iy += 1;
+        }
+
+        Some(Point::new(self.x, self.y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        let points: Vec<Point> = Line::new(Point::new(0, 0), Point::new(3, 1)).collect();
+
+        assert_eq!(
+            points,
+            vec![
+                Point::new(0, 0),
+                Point::new(1, 0),
+                Point::new(2, 1),
+                Point::new(3, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_corners() {
+        let from = Point::new(2, 2);
+        let to = Point::new(0, 0);
+
+        let line: Vec<Point> = Line::new(from, to).collect();
+        let supercover: Vec<Point> = Line::supercover(from, to).collect();
+
+        assert_eq!(line, vec![from, Point::new(1, 1), to]);
+        assert_eq!(
+            supercover,
+            vec![
+                from,
+                Point::new(1, 2),
+                Point::new(2, 1),
+                Point::new(1, 1),
+                Point::new(0, 1),
+                Point::new(1, 0),
+                to
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_point() {
+        let point = Point::new(-1, 4);
+
+        assert_eq!(Line::new(point, point).collect::<Vec<_>>(), vec![point]);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::Point;
+
+/// Read access to a map that the A* searches can explore, so they can run
+/// directly over storage other than `Grid`, such as a game engine's own
+/// tilemap.
+///
+/// A map covers the rectangle of `width` by `height` nodes whose top-left
+/// corner is `origin`. Searches never step outside it, and only ask about
+/// points within it.
+pub trait Map {
+    /// Returns the number of columns the map covers.
+    fn width(&self) -> usize;
+
+    /// Returns the number of rows the map covers.
+    fn height(&self) -> usize;
+
+    /// Returns the top-left corner of the map.
+    fn origin(&self) -> Point {
+        Point::new(0, 0)
+    }
+
+    /// Returns whether the node at `point` can be entered.
+    fn is_walkable(&self, point: Point) -> bool;
+
+    /// Returns the cost of entering the walkable node at `point`.
+    ///
+    /// Costs must be at least 1, or the Manhattan distance heuristic may
+    /// overestimate and searches may not return the cheapest path.
+    /// Path costs saturate, so a cost of `usize::MAX` makes a node
+    /// impassable.
+    fn cost(&self, point: Point) -> usize {
+        let _ = point;
+        1
+    }
+
+    /// Returns a label that is equal for two points only if they can reach
+    /// each other, or `None` if it is not known. Searches use it to reject
+    /// unreachable goals without exploring.
+    fn region(&self, point: Point) -> Option<usize> {
+        let _ = point;
+        None
+    }
+
+    /// Returns whether `point` lies within the map.
+    fn contains(&self, point: Point) -> bool {
+        map_index(self, point).is_some()
+    }
+}
+
+impl<M: Map + ?Sized> Map for &M {
+    fn width(&self) -> usize {
+        (**self).width()
+    }
+
+    fn height(&self) -> usize {
+        (**self).height()
+    }
+
+    fn origin(&self) -> Point {
+        (**self).origin()
+    }
+
+    fn is_walkable(&self, point: Point) -> bool {
+        (**self).is_walkable(point)
+    }
+
+    fn cost(&self, point: Point) -> usize {
+        (**self).cost(point)
+    }
+
+    fn region(&self, point: Point) -> Option<usize> {
+        (**self).region(point)
+    }
+
+    fn contains(&self, point: Point) -> bool {
+        (**self).contains(point)
+    }
+}
+
+/// Returns the row-major index of `point` within the bounds of `map`, or
+/// `None` if it lies outside them.
+#[inline]
+pub(crate) fn map_index<M: Map + ?Sized>(map: &M, point: Point) -> Option<usize> {
+    let origin = map.origin();
+    let x = point.x.checked_sub(origin.x)?;
+    let y = point.y.checked_sub(origin.y)?;
+
+    let in_bounds = x >= 0 && y >= 0 && (x as usize) < map.width() && (y as usize) < map.height();
+    in_bounds.then(|| y as usize * map.width() + x as usize)
+}
+
+/// Returns the region of `point` if it lies within `map`, so maps are never
+/// asked about points outside their bounds.
+#[inline]
+pub(crate) fn map_region<M: Map + ?Sized>(map: &M, point: Point) -> Option<usize> {
+    map.contains(point).then(|| map.region(point)).flatten()
+}
@@ -0,0 +1,141 @@
+use std::{num::NonZeroUsize, thread};
+
+use crate::{reachable, Map, Point};
+
+/// The travel cost between every pair of a set of points. Created by
+/// `distance_matrix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistanceMatrix {
+    len: usize,
+    distances: Vec<Option<usize>>,
+}
+
+impl DistanceMatrix {
+    /// Returns the number of points the matrix was computed for.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the matrix was computed for no points.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the cost of the shortest path from the point at index `from`
+    /// to the point at index `to`, or `None` if it is unreachable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    #[must_use]
+    pub fn get(&self, from: usize, to: usize) -> Option<usize> {
+        assert!(from < self.len && to < self.len, "index out of bounds");
+        self.distances[from * self.len + to]
+    }
+
+    /// Returns the costs from the point at index `from` to every point, in
+    /// the order they were given.
+    #[must_use]
+    pub fn row(&self, from: usize) -> &[Option<usize>] {
+        &self.distances[from * self.len..(from + 1) * self.len]
+    }
+}
+
+/// Computes the cost of the cheapest path between every pair of `points`,
+/// using a single flood from each point rather than one search per pair.
+/// Costs are the total `Map::cost` of the nodes entered, as in `reachable`.
+#[must_use]
+pub fn distance_matrix<M: Map + ?Sized>(grid: &M, points: &[Point]) -> DistanceMatrix {
+    let mut distances = vec![None; points.len() * points.len()];
+
+    for (from, row) in distances.chunks_mut(points.len().max(1)).enumerate() {
+        fill_row(grid, points, from, row);
+    }
+
+    DistanceMatrix {
+        len: points.len(),
+        distances,
+    }
+}
+
+/// Like `distance_matrix`, but floods from several points at once on as many
+/// threads as the system has available.
+#[must_use]
+pub fn distance_matrix_parallel<M: Map + Sync + ?Sized>(
+    grid: &M,
+    points: &[Point],
+) -> DistanceMatrix {
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let rows_per_thread = points.len().div_ceil(threads).max(1);
+
+    let mut distances = vec![None; points.len() * points.len()];
+    let row_len = points.len().max(1);
+
+    thread::scope(|scope| {
+        for (chunk, rows) in distances.chunks_mut(rows_per_thread * row_len).enumerate() {
+            scope.spawn(move || {
+                for (i, row) in rows.chunks_mut(row_len).enumerate() {
+                    fill_row(grid, points, chunk * rows_per_thread + i, row);
+                }
+            });
+        }
+    });
+
+    DistanceMatrix {
+        len: points.len(),
+        distances,
+    }
+}
+
+/// Fills `row` with the costs from `points[from]` to every point.
+fn fill_row<M: Map + ?Sized>(grid: &M, points: &[Point], from: usize, row: &mut [Option<usize>]) {
+    let flood = reachable(grid, points[from], usize::MAX);
+
+    for (distance, &to) in row.iter_mut().zip(points) {
+        *distance = flood.cost(to);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid;
+
+    #[test]
+    fn test_distance_matrix() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, true,  false],
+            vec![false, true,  false, true,  false],
+            vec![false, false, false, true,  false],
+        ]);
+
+        let points = [Point::new(0, 0), Point::new(2, 2), Point::new(4, 0)];
+        let matrix = distance_matrix(&grid, &points);
+
+        assert_eq!(matrix.len(), 3);
+        assert_eq!(matrix.get(0, 0), Some(0));
+        assert_eq!(matrix.get(0, 1), Some(4));
+        assert_eq!(matrix.get(1, 0), Some(4));
+        assert_eq!(matrix.get(0, 2), None);
+        assert_eq!(matrix.row(2), &[None, None, Some(0)]);
+    }
+
+    #[test]
+    fn test_parallel_matches() {
+        let mut grid = Grid::new(20, 20);
+        for y in 0..19 {
+            grid.set(10, y, true);
+        }
+
+        let points: Vec<Point> = (0..20).map(|i| Point::new(i, (i * 7) % 20)).collect();
+
+        assert_eq!(
+            distance_matrix_parallel(&grid, &points),
+            distance_matrix(&grid, &points)
+        );
+        assert!(distance_matrix_parallel(&grid, &[]).is_empty());
+    }
+}
@@ -85,3 +85,31 @@ impl From<Point> for Node {
         Self::new(point.x, point.y)
     }
 }
+
+/// An entry in an open set ordered by a floating-point f-cost, so that
+/// `BinaryHeap` pops the lowest f-cost first.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OpenNode {
+    pub(crate) f: f64,
+    pub(crate) index: usize,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.f.total_cmp(&self.f)
+    }
+}
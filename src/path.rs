@@ -0,0 +1,41 @@
+use crate::Point;
+
+/// The result of a search that may fall back to a path which does not reach
+/// the requested goal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathResult {
+    /// A shortest path from the start to the goal.
+    Complete(Vec<Point>),
+    /// A path from the start to the reachable point closest to the goal.
+    Partial(Vec<Point>),
+}
+
+impl PathResult {
+    /// Returns whether the path reaches the goal.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Complete(_))
+    }
+
+    /// Returns whether the path stops short of the goal.
+    #[must_use]
+    pub fn is_partial(&self) -> bool {
+        matches!(self, Self::Partial(_))
+    }
+
+    /// Returns the points of the path, regardless of whether it is complete.
+    #[must_use]
+    pub fn path(&self) -> &[Point] {
+        match self {
+            Self::Complete(path) | Self::Partial(path) => path,
+        }
+    }
+
+    /// Consumes the result, returning the points of the path.
+    #[must_use]
+    pub fn into_path(self) -> Vec<Point> {
+        match self {
+            Self::Complete(path) | Self::Partial(path) => path,
+        }
+    }
+}
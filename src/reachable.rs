@@ -0,0 +1,273 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{get_neighbor_points, map::map_index, Map, Point};
+
+/// Every point reachable from an origin within a movement budget, along with
+/// the cost of reaching it. Created by `reachable`.
+#[derive(Debug, Clone)]
+pub struct Reachable {
+    origin: Point,
+    /// The top-left corner of the map the area was computed on.
+    map_origin: Point,
+    width: usize,
+    height: usize,
+    costs: Vec<usize>,
+    parents: Vec<Option<usize>>,
+    /// Indices of the reachable points, in the order they were settled.
+    settled: Vec<usize>,
+}
+
+impl Reachable {
+    /// Returns the point the area was computed from.
+    #[must_use]
+    pub fn origin(&self) -> Point {
+        self.origin
+    }
+
+    /// Returns the number of reachable points, including the origin.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.settled.len()
+    }
+
+    /// Returns whether no points are reachable, which only happens when the
+    /// origin is blocked or lies outside of the map.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.settled.is_empty()
+    }
+
+    /// Returns whether `point` can be reached within the budget.
+    #[must_use]
+    pub fn contains(&self, point: Point) -> bool {
+        self.cost(point).is_some()
+    }
+
+    /// Returns the cost of the cheapest path to `point`, or `None` if it cannot
+    /// be reached within the budget.
+    #[must_use]
+    pub fn cost(&self, point: Point) -> Option<usize> {
+        self.index(point)
+            .map(|i| self.costs[i])
+            .filter(|&cost| cost != usize::MAX)
+    }
+
+    /// Returns an iterator over every reachable point and its cost, in order
+    /// of increasing cost.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, usize)> + '_ {
+        self.settled
+            .iter()
+            .map(|&index| (self.point(index), self.costs[index]))
+    }
+
+    /// Returns the cheapest path from the origin to `point`, or `None` if it
+    /// cannot be reached within the budget.
+    #[must_use]
+    pub fn path_to(&self, point: Point) -> Option<Vec<Point>> {
+        let mut index = self.index(point)?;
+        if self.costs[index] == usize::MAX {
+            return None;
+        }
+
+        let mut path = vec![point];
+
+        while let Some(parent) = self.parents[index] {
+            index = parent;
+            path.push(self.point(index));
+        }
+
+        path.reverse();
+        Some(path)
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        let x = point.x.checked_sub(self.map_origin.x)?;
+        let y = point.y.checked_sub(self.map_origin.y)?;
+
+        let in_bounds = x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height;
+        in_bounds.then(|| y as usize * self.width + x as usize)
+    }
+
+    fn point(&self, index: usize) -> Point {
+        Point::new(
+            self.map_origin.x + (index % self.width) as isize,
+            self.map_origin.y + (index / self.width) as isize,
+        )
+    }
+}
+
+/// Finds every point that can be reached from `origin` with a total movement
+/// cost of at most `budget`, such as the cells a unit can move to this turn.
+/// The cost of a path is the total `Map::cost` of the nodes it enters.
+///
+/// The origin is always included at a cost of zero, as long as it is walkable.
+/// A blocked origin, or one outside the map, reaches nothing, so the result
+/// is empty.
+#[must_use]
+pub fn reachable<M: Map + ?Sized>(grid: &M, origin: Point, budget: usize) -> Reachable {
+    let width = grid.width();
+    let height = grid.height();
+    let capacity = width * height;
+
+    let mut reachable = Reachable {
+        origin,
+        map_origin: grid.origin(),
+        width,
+        height,
+        costs: vec![usize::MAX; capacity],
+        parents: vec![None; capacity],
+        settled: Vec::new(),
+    };
+
+    let Some(origin_index) = map_index(grid, origin).filter(|_| grid.is_walkable(origin)) else {
+        return reachable;
+    };
+
+    // Dijkstra's algorithm, so the flood settles points in order of cost.
+    let mut open: BinaryHeap<Reverse<(usize, usize)>> = BinaryHeap::new();
+    let mut closed = vec![false; capacity];
+    reachable.costs[origin_index] = 0;
+    open.push(Reverse((0, origin_index)));
+
+    while let Some(Reverse((cost, index))) = open.pop() {
+        if closed[index] {
+            continue;
+        }
+        closed[index] = true;
+
+        let point = reachable.point(index);
+        reachable.settled.push(index);
+
+        for neighbor in get_neighbor_points(grid, point) {
+            let Some(neighbor_index) = map_index(grid, neighbor) else {
+                continue;
+            };
+
+            // Saturates, so the largest costs leave a node unreachable rather
+            // than wrapping around to cheap ones.
+            let neighbor_cost = cost.saturating_add(grid.cost(neighbor));
+
+            if neighbor_cost > budget || neighbor_cost >= reachable.costs[neighbor_index] {
+                continue;
+            }
+
+            reachable.costs[neighbor_index] = neighbor_cost;
+            reachable.parents[neighbor_index] = Some(index);
+            open.push(Reverse((neighbor_cost, neighbor_index)));
+        }
+    }
+
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid;
+
+    #[test]
+    fn test_reachable_within_budget() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false],
+            vec![false, true,  true,  false],
+            vec![false, false, false, false],
+        ]);
+
+        let origin = Point::new(0, 0);
+        let area = reachable(&grid, origin, 3);
+
+        assert_eq!(area.len(), 7);
+        assert_eq!(area.cost(origin), Some(0));
+        assert_eq!(area.cost(Point::new(3, 0)), Some(3));
+        assert_eq!(area.cost(Point::new(2, 2)), None);
+        assert!(!area.contains(Point::new(1, 1)));
+        assert!(area.iter().map(|(_, cost)| cost).is_sorted());
+    }
+
+    #[test]
+    fn test_reachable_path() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, true,  false],
+            vec![false, true,  false],
+            vec![false, false, false],
+        ]);
+
+        let area = reachable(&grid, Point::new(0, 0), 10);
+        let path = area.path_to(Point::new(2, 0)).unwrap();
+
+        assert_eq!(path.len(), 7);
+        assert_eq!(path.first(), Some(&Point::new(0, 0)));
+        assert_eq!(path.last(), Some(&Point::new(2, 0)));
+        assert!(area.path_to(Point::new(1, 0)).is_none());
+        assert!(area.path_to(Point::new(5, 5)).is_none());
+    }
+
+    #[test]
+    fn test_reachable_blocked_origin() {
+        let mut grid = Grid::new(4, 4);
+        grid.set(1, 1, true);
+
+        let area = reachable(&grid, Point::new(1, 1), 10);
+
+        assert!(area.is_empty());
+        assert_eq!(area.cost(Point::new(1, 1)), None);
+        assert!(area.path_to(Point::new(0, 0)).is_none());
+    }
+
+    /// A map offset into negative coordinates, where the middle column costs
+    /// five to enter.
+    struct Mud;
+
+    impl Map for Mud {
+        fn width(&self) -> usize {
+            3
+        }
+
+        fn height(&self) -> usize {
+            3
+        }
+
+        fn origin(&self) -> Point {
+            Point::new(-1, -1)
+        }
+
+        fn is_walkable(&self, _point: Point) -> bool {
+            true
+        }
+
+        fn cost(&self, point: Point) -> usize {
+            if point.x == 0 {
+                5
+            } else {
+                1
+            }
+        }
+    }
+
+    #[test]
+    fn test_reachable_costs() {
+        let origin = Point::new(-1, -1);
+        let area = reachable(&Mud, origin, 7);
+
+        assert_eq!(area.cost(origin), Some(0));
+        assert_eq!(area.cost(Point::new(0, -1)), Some(5));
+        assert_eq!(area.cost(Point::new(1, -1)), Some(6));
+        assert_eq!(area.cost(Point::new(-1, 1)), Some(2));
+        assert_eq!(area.cost(Point::new(1, 1)), None);
+        assert_eq!(area.len(), 8);
+        assert!(area.iter().map(|(_, cost)| cost).is_sorted());
+
+        let path = area.path_to(Point::new(0, 1)).unwrap();
+        assert_eq!(
+            path,
+            [
+                origin,
+                Point::new(-1, 0),
+                Point::new(-1, 1),
+                Point::new(0, 1)
+            ]
+        );
+    }
+}
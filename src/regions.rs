@@ -0,0 +1,285 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Label given to blocked cells, which do not belong to any region.
+const NONE: usize = usize::MAX;
+
+/// The eight cells around a cell, in order around it, so that consecutive
+/// entries (including the last and first) are cardinal neighbors of each
+/// other. Cardinal directions are at even positions.
+const RING: [(isize, isize); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// Connected-component labels for the walkable cells of a `Grid`. Two cells
+/// share a label if, and only if, there is a path between them.
+#[derive(Debug, Clone)]
+pub(crate) struct Regions {
+    labels: Vec<usize>,
+    /// Number of cells carrying each label, indexed by label.
+    sizes: Vec<usize>,
+}
+
+impl Regions {
+    /// Labels every walkable cell in `nodes`.
+    pub(crate) fn new(nodes: &[bool], width: usize) -> Self {
+        let mut regions = Self {
+            labels: vec![NONE; nodes.len()],
+            sizes: Vec::new(),
+        };
+
+        for index in 0..nodes.len() {
+            if !nodes[index] && regions.labels[index] == NONE {
+                let label = regions.fresh_label();
+                regions.relabel(nodes, width, index, NONE, label);
+            }
+        }
+
+        regions
+    }
+
+    /// Returns the label of the cell at `index`, or `None` if it is blocked.
+    pub(crate) fn get(&self, index: usize) -> Option<usize> {
+        Some(self.labels[index]).filter(|&label| label != NONE)
+    }
+
+    /// Relabels the cells affected by the cell at `index` changing state.
+    /// `nodes` must already contain the new state.
+    ///
+    /// Most changes take constant time. Only merging regions relabels the
+    /// smaller ones, and only a blocked cell that may split its region
+    /// searches it.
+    pub(crate) fn update(&mut self, nodes: &[bool], width: usize, index: usize) {
+        if nodes[index] {
+            self.block(nodes, width, index);
+        } else {
+            self.open(nodes, width, index);
+        }
+    }
+
+    /// Joins an opened cell to the regions around it, merging them into the
+    /// largest one.
+    fn open(&mut self, nodes: &[bool], width: usize, index: usize) {
+        let mut labels: Vec<usize> = neighbors(nodes.len(), width, index)
+            .map(|neighbor| self.labels[neighbor])
+            .filter(|&label| label != NONE)
+            .collect();
+        labels.sort_unstable();
+        labels.dedup();
+
+        let Some(&largest) = labels.iter().max_by_key(|&&label| self.sizes[label]) else {
+            let label = self.fresh_label();
+            self.labels[index] = label;
+            self.sizes[label] = 1;
+            return;
+        };
+
+        self.labels[index] = largest;
+        self.sizes[largest] += 1;
+
+        for label in labels.into_iter().filter(|&label| label != largest) {
+            let start = neighbors(nodes.len(), width, index)
+                .find(|&neighbor| self.labels[neighbor] == label);
+
+            if let Some(start) = start {
+                self.relabel(nodes, width, start, label, largest);
+            }
+        }
+    }
+
+    /// Removes a blocked cell from its region, splitting the region if the
+    /// cell was the only connection between parts of it.
+    fn block(&mut self, nodes: &[bool], width: usize, index: usize) {
+        let label = self.labels[index];
+        self.labels[index] = NONE;
+        if label == NONE {
+            return;
+        }
+        self.sizes[label] -= 1;
+
+        // Open neighbors that are connected through the ring of cells around
+        // this one stay connected, so only one of each such group can end up
+        // in a separate region.
+        let starts = ring_groups(nodes, width, index);
+        if starts.len() <= 1 {
+            return;
+        }
+
+        // Search from every group at once, one cell at a time each, so that
+        // the search of a region that has split off finishes after visiting
+        // no more cells than the region holds.
+        let mut owners: HashMap<usize, usize> = HashMap::new();
+        let mut queues: Vec<VecDeque<usize>> = Vec::new();
+        let mut visited: Vec<Vec<usize>> = Vec::new();
+        let mut groups: Vec<usize> = (0..starts.len()).collect();
+        let mut closed = vec![false; starts.len()];
+
+        for (search, &start) in starts.iter().enumerate() {
+            owners.insert(start, search);
+            queues.push(VecDeque::from([start]));
+            visited.push(vec![start]);
+        }
+
+        let find = |groups: &[usize], mut search: usize| {
+            while groups[search] != search {
+                search = groups[search];
+            }
+            search
+        };
+
+        loop {
+            let open_groups = {
+                let mut open: Vec<usize> = (0..starts.len())
+                    .filter(|&search| !closed[search])
+                    .map(|search| find(&groups, search))
+                    .collect();
+                open.sort_unstable();
+                open.dedup();
+                open.len()
+            };
+
+            // The last group still searching keeps the original label.
+            if open_groups <= 1 {
+                return;
+            }
+
+            for search in 0..starts.len() {
+                if closed[search] {
+                    continue;
+                }
+
+                if let Some(cell) = queues[search].pop_front() {
+                    for neighbor in neighbors(nodes.len(), width, cell) {
+                        if nodes[neighbor] {
+                            continue;
+                        }
+
+                        match owners.get(&neighbor) {
+                            Some(&other) => {
+                                let (a, b) = (find(&groups, search), find(&groups, other));
+                                groups[a.max(b)] = a.min(b);
+                            }
+                            None => {
+                                owners.insert(neighbor, search);
+                                queues[search].push_back(neighbor);
+                                visited[search].push(neighbor);
+                            }
+                        }
+                    }
+                }
+
+                // A group whose searches have all run dry has been fully
+                // explored without reaching the others, so it split off.
+                let group = find(&groups, search);
+                let members: Vec<usize> = (0..starts.len())
+                    .filter(|&other| find(&groups, other) == group)
+                    .collect();
+
+                if members.iter().all(|&member| queues[member].is_empty()) {
+                    let new_label = self.fresh_label();
+
+                    for member in members {
+                        closed[member] = true;
+                        for &cell in &visited[member] {
+                            self.labels[cell] = new_label;
+                        }
+                        self.sizes[new_label] += visited[member].len();
+                        self.sizes[label] -= visited[member].len();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gives every open cell connected to `start` that is labeled `from`,
+    /// including `start` itself, the label `to`.
+    fn relabel(&mut self, nodes: &[bool], width: usize, start: usize, from: usize, to: usize) {
+        let mut stack = vec![start];
+        let mut count = 1;
+        self.labels[start] = to;
+
+        while let Some(index) = stack.pop() {
+            for neighbor in neighbors(nodes.len(), width, index) {
+                if nodes[neighbor] || self.labels[neighbor] != from {
+                    continue;
+                }
+
+                self.labels[neighbor] = to;
+                count += 1;
+                stack.push(neighbor);
+            }
+        }
+
+        if from != NONE {
+            self.sizes[from] -= count;
+        }
+        self.sizes[to] += count;
+    }
+
+    /// Returns a label no cell has been given yet.
+    fn fresh_label(&mut self) -> usize {
+        self.sizes.push(0);
+        self.sizes.len() - 1
+    }
+}
+
+/// Returns one open cardinal neighbor of `index` for each group of them that
+/// is connected through the ring of eight cells around `index`.
+fn ring_groups(nodes: &[bool], width: usize, index: usize) -> Vec<usize> {
+    let height = nodes.len() / width;
+    let x = (index % width) as isize;
+    let y = (index / width) as isize;
+
+    let ring = RING.map(|(dx, dy)| {
+        let (x, y) = (x + dx, y + dy);
+        let in_bounds = x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height;
+        in_bounds
+            .then(|| y as usize * width + x as usize)
+            .filter(|&neighbor| !nodes[neighbor])
+    });
+
+    // Walk around the ring from a blocked cell, so that every run of open
+    // cells is seen from its start.
+    let Some(first_blocked) = ring.iter().position(Option::is_none) else {
+        return vec![ring[0].unwrap_or_default()];
+    };
+
+    let mut starts = Vec::new();
+    let mut in_run = false;
+
+    for step in 1..=RING.len() {
+        let position = (first_blocked + step) % RING.len();
+
+        match ring[position] {
+            None => in_run = false,
+            Some(cell) if position.is_multiple_of(2) && !in_run => {
+                starts.push(cell);
+                in_run = true;
+            }
+            Some(_) => {}
+        }
+    }
+
+    starts
+}
+
+/// Returns the indices of the cardinal neighbors of `index` in a grid of
+/// `len` cells and the given `width`.
+fn neighbors(len: usize, width: usize, index: usize) -> impl Iterator<Item = usize> {
+    let x = index % width;
+
+    [
+        (index >= width).then(|| index - width),
+        (index + width < len).then(|| index + width),
+        (x > 0).then(|| index - 1),
+        (x + 1 < width).then(|| index + 1),
+    ]
+    .into_iter()
+    .flatten()
+}
@@ -0,0 +1,155 @@
+use crate::{reachable, Map, Point, Reachable};
+
+/// How `route` may order the waypoints between the first and the last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VisitOrder {
+    /// Visit the waypoints in the order given.
+    #[default]
+    Fixed,
+    /// Reorder the intermediate waypoints to shorten the route, using a
+    /// nearest-neighbor tour improved with 2-opt. The result is usually, but
+    /// not always, the shortest order.
+    Optimized,
+}
+
+/// A path passing through several waypoints. Created by `route`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// Indices into the waypoints passed to `route`, in the order visited.
+    pub order: Vec<usize>,
+    /// The points of the route, from the first waypoint to the last.
+    pub path: Vec<Point>,
+}
+
+/// Finds a path that starts at the first of `waypoints`, passes through each
+/// of the others, and ends at the last. With `VisitOrder::Optimized`, the
+/// intermediate waypoints may be visited in any order that shortens the
+/// route.
+///
+/// Legs are the cheapest paths by total `Map::cost`, as in `reachable`.
+///
+/// Returns `None` if `waypoints` is empty, or if any waypoint cannot reach
+/// the next one.
+#[must_use]
+pub fn route<M: Map + ?Sized>(grid: &M, waypoints: &[Point], order: VisitOrder) -> Option<Route> {
+    if waypoints.is_empty() {
+        return None;
+    }
+
+    // One flood per waypoint gives both the distances between every pair and
+    // the paths between them.
+    let floods: Vec<Reachable> = waypoints
+        .iter()
+        .map(|&waypoint| reachable(grid, waypoint, usize::MAX))
+        .collect();
+
+    let distance = |from: usize, to: usize| floods[from].cost(waypoints[to]);
+
+    let mut visits: Vec<usize> = (0..waypoints.len()).collect();
+    if order == VisitOrder::Optimized {
+        optimize(&mut visits, |from, to| {
+            distance(from, to).unwrap_or(usize::MAX)
+        });
+    }
+
+    let mut path = vec![waypoints[visits[0]]];
+    for pair in visits.windows(2) {
+        let leg = floods[pair[0]].path_to(waypoints[pair[1]])?;
+        path.extend(leg.into_iter().skip(1));
+    }
+
+    Some(Route {
+        order: visits,
+        path,
+    })
+}
+
+/// Reorders all but the first and last of `visits` to shorten the tour.
+fn optimize(visits: &mut [usize], distance: impl Fn(usize, usize) -> usize) {
+    let len = visits.len();
+    if len <= 3 {
+        return;
+    }
+
+    // Greedily visit the closest remaining waypoint next.
+    for i in 1..len - 2 {
+        let previous = visits[i - 1];
+        let nearest = (i..len - 1)
+            .min_by_key(|&j| distance(previous, visits[j]))
+            .unwrap_or(i);
+        visits.swap(i, nearest);
+    }
+
+    // Then reverse any stretch of the tour that makes it shorter, until no
+    // reversal helps. Unreachable legs are treated as infinitely long.
+    let cost = |a: usize, b: usize| distance(a, b) as u128;
+    let mut improved = true;
+    while improved {
+        improved = false;
+
+        for i in 1..len - 2 {
+            for j in i + 1..len - 1 {
+                let before = cost(visits[i - 1], visits[i]) + cost(visits[j], visits[j + 1]);
+                let after = cost(visits[i - 1], visits[j]) + cost(visits[i], visits[j + 1]);
+
+                if after < before {
+                    visits[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid;
+
+    #[test]
+    fn test_fixed_order() {
+        let grid = Grid::new(5, 5);
+        let waypoints = [Point::new(0, 0), Point::new(4, 0), Point::new(0, 4)];
+
+        let route = route(&grid, &waypoints, VisitOrder::Fixed).unwrap();
+
+        assert_eq!(route.order, vec![0, 1, 2]);
+        assert_eq!(route.path.len(), 13);
+        assert_eq!(route.path.first(), Some(&waypoints[0]));
+        assert_eq!(route.path[4], waypoints[1]);
+        assert_eq!(route.path.last(), Some(&waypoints[2]));
+    }
+
+    #[test]
+    fn test_optimized_order() {
+        let grid = Grid::new(10, 1);
+        let waypoints = [
+            Point::new(0, 0),
+            Point::new(8, 0),
+            Point::new(2, 0),
+            Point::new(6, 0),
+            Point::new(4, 0),
+            Point::new(9, 0),
+        ];
+
+        let fixed = route(&grid, &waypoints, VisitOrder::Fixed).unwrap();
+        let optimized = route(&grid, &waypoints, VisitOrder::Optimized).unwrap();
+
+        assert_eq!(optimized.order, vec![0, 2, 4, 3, 1, 5]);
+        assert_eq!(optimized.path.len(), 10);
+        assert!(optimized.path.len() < fixed.path.len());
+    }
+
+    #[test]
+    fn test_unreachable_waypoint() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, true, false],
+        ]);
+
+        let waypoints = [Point::new(0, 0), Point::new(2, 0)];
+
+        assert!(route(&grid, &waypoints, VisitOrder::Optimized).is_none());
+        assert!(route(&grid, &[], VisitOrder::Fixed).is_none());
+    }
+}
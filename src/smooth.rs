@@ -0,0 +1,81 @@
+use crate::{Grid, Point};
+
+/// Removes redundant points from a path, such as one returned by `astar`, by
+/// "pulling the string" taut: each waypoint is followed by the furthest point
+/// along the path that can be reached in a straight line.
+///
+/// The first and last points are always kept, and the straight line between
+/// any two consecutive points of the result only passes through walkable
+/// nodes.
+#[must_use]
+pub fn smooth_path(grid: &Grid, path: &[Point]) -> Vec<Point> {
+    let Some((&first, rest)) = path.split_first() else {
+        return Vec::new();
+    };
+
+    let mut smoothed = vec![first];
+    let mut anchor = first;
+    let mut previous = first;
+
+    for &point in rest {
+        if point != previous && !grid.line_of_sight(anchor, point) {
+            smoothed.push(previous);
+            anchor = previous;
+        }
+
+        previous = point;
+    }
+
+    if previous != anchor {
+        smoothed.push(previous);
+    }
+
+    smoothed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar;
+
+    #[test]
+    fn test_straight_line() {
+        let grid = Grid::new(5, 5);
+        let start = Point::new(0, 0);
+        let end = Point::new(4, 3);
+
+        let path = astar(&grid, start, end).unwrap();
+
+        assert_eq!(smooth_path(&grid, &path), vec![start, end]);
+    }
+
+    #[test]
+    fn test_around_corner() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false],
+            vec![true,  true,  true,  false],
+            vec![false, false, false, false],
+        ]);
+
+        let start = Point::new(0, 0);
+        let end = Point::new(0, 2);
+
+        let path = astar(&grid, start, end).unwrap();
+        let smoothed = smooth_path(&grid, &path);
+
+        assert_eq!(
+            smoothed,
+            vec![start, Point::new(3, 0), Point::new(3, 2), end]
+        );
+    }
+
+    #[test]
+    fn test_trivial_paths() {
+        let grid = Grid::new(2, 2);
+        let point = Point::new(1, 1);
+
+        assert!(smooth_path(&grid, &[]).is_empty());
+        assert_eq!(smooth_path(&grid, &[point]), vec![point]);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{manhattan_distance, retrace_path, Grid, Point, SearchState, Step};
+
+/// The progress of a `SteppedSearch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchStatus {
+    /// The search has not finished; call `SteppedSearch::step` again.
+    InProgress,
+    /// A shortest path from the start to the end was found.
+    Found(Vec<Point>),
+    /// The end is unreachable.
+    Failed,
+    /// The search was cancelled before it finished.
+    Cancelled,
+}
+
+/// An A* search that can be advanced a limited number of node expansions at
+/// a time, such as a few hundred per frame, instead of running to completion
+/// in a single call like `astar`.
+///
+/// All of the search state is kept between calls to `SteppedSearch::step`.
+/// The grid passed to each call must not change while the search is in
+/// progress, or the resulting path may be invalid.
+#[derive(Debug, Clone)]
+pub struct SteppedSearch {
+    end: Point,
+    state: Option<SearchState>,
+    status: SearchStatus,
+}
+
+impl SteppedSearch {
+    /// Prepares a search from `start` to `end` without expanding any nodes.
+    ///
+    /// A `start` outside the grid fails immediately. So does an `end` in a
+    /// different region than `start`, if the grid has computed region labels;
+    /// see `Grid::compute_regions`.
+    #[must_use]
+    pub fn new(grid: &Grid, start: Point, end: Point) -> Self {
+        let unreachable = match (grid.region(start), grid.region(end)) {
+            (Some(a), Some(b)) => a != b,
+            _ => false,
+        };
+
+        if unreachable || grid.index(start.x, start.y).is_none() {
+            return Self {
+                end,
+                state: None,
+                status: SearchStatus::Failed,
+            };
+        }
+
+        Self {
+            end,
+            state: Some(SearchState::new(
+                grid,
+                start,
+                manhattan_distance(&start, &end),
+            )),
+            status: SearchStatus::InProgress,
+        }
+    }
+
+    /// Expands up to `budget` nodes, returning the resulting status. Once the
+    /// search has finished, further calls return the same status without
+    /// doing any work.
+    pub fn step(&mut self, grid: &Grid, budget: usize) -> SearchStatus {
+        let Some(state) = &mut self.state else {
+            return self.status.clone();
+        };
+
+        let end = self.end;
+        let step = state.step(
+            grid,
+            budget,
+            |point| manhattan_distance(&point, &end),
+            |point| point == end,
+            |_| true,
+        );
+
+        self.status = match step {
+            Step::Found(index) => SearchStatus::Found(retrace_path(&state.nodes, index)),
+            Step::Exhausted => SearchStatus::Failed,
+            Step::Paused => return SearchStatus::InProgress,
+        };
+
+        // The search is over, so its memory can be released early.
+        self.state = None;
+        self.status.clone()
+    }
+
+    /// Cancels the search and releases its memory. Has no effect if the
+    /// search has already finished.
+    pub fn cancel(&mut self) {
+        if self.state.take().is_some() {
+            self.status = SearchStatus::Cancelled;
+        }
+    }
+
+    /// Returns the current status without doing any work.
+    #[must_use]
+    pub fn status(&self) -> &SearchStatus {
+        &self.status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar;
+
+    #[test]
+    fn test_resumes_until_found() {
+        let grid = Grid::new(10, 10);
+        let start = Point::new(0, 0);
+        let end = Point::new(9, 9);
+
+        let mut search = SteppedSearch::new(&grid, start, end);
+        let mut calls = 0;
+
+        let path = loop {
+            calls += 1;
+            match search.step(&grid, 5) {
+                SearchStatus::InProgress => continue,
+                SearchStatus::Found(path) => break path,
+                status => panic!("Unexpected status {status:?}"),
+            }
+        };
+
+        assert!(calls > 1);
+        assert_eq!(Some(path.clone()), astar(&grid, start, end));
+        assert_eq!(search.status(), &SearchStatus::Found(path));
+    }
+
+    #[test]
+    fn test_failed_and_cancelled() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, true,  false],
+            vec![false, true,  false],
+        ]);
+
+        let mut search = SteppedSearch::new(&grid, Point::new(0, 0), Point::new(2, 0));
+        assert_eq!(search.step(&grid, 100), SearchStatus::Failed);
+
+        let mut search = SteppedSearch::new(&grid, Point::new(0, 0), Point::new(0, 1));
+        search.cancel();
+        assert_eq!(search.step(&grid, 100), SearchStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_start_outside_grid() {
+        let grid = Grid::new(4, 4);
+
+        for start in [Point::new(-1, 0), Point::new(4, 0), Point::new(0, 4)] {
+            let mut search = SteppedSearch::new(&grid, start, Point::new(3, 3));
+            assert_eq!(search.status(), &SearchStatus::Failed);
+            assert_eq!(search.step(&grid, 100), SearchStatus::Failed);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::BinaryHeap;
+
+use crate::{get_neighbor_points, node::OpenNode, point_to_index, Grid, Point};
+
+/// Attempts to find a short any-angle path from `start` to `end` using the
+/// Theta* algorithm. Returns `None` if no path is found.
+///
+/// Unlike `astar`, consecutive points in the returned path are waypoints that
+/// need not be adjacent; the straight line between any two of them only
+/// passes through walkable nodes.
+#[must_use]
+pub fn theta_star(grid: &Grid, start: Point, end: Point) -> Option<Vec<Point>> {
+    search(grid, start, end, false)
+}
+
+/// Attempts to find a short any-angle path from `start` to `end` using the
+/// Lazy Theta* algorithm. Returns `None` if no path is found.
+///
+/// This returns paths of the same kind as `theta_star`, but defers
+/// line-of-sight checks until a node is expanded, which performs far fewer
+/// of them at the cost of slightly longer paths in some cases.
+#[must_use]
+pub fn lazy_theta_star(grid: &Grid, start: Point, end: Point) -> Option<Vec<Point>> {
+    search(grid, start, end, true)
+}
+
+fn search(grid: &Grid, start: Point, end: Point, lazy: bool) -> Option<Vec<Point>> {
+    let width = grid.width();
+    let capacity = width * grid.height();
+
+    let to_point = |index: usize| Point::new((index % width) as isize, (index / width) as isize);
+
+    let mut open_nodes = BinaryHeap::new();
+    let mut closed_nodes = vec![false; capacity];
+    let mut g_scores = vec![f64::INFINITY; capacity];
+    let mut parents = vec![usize::MAX; capacity];
+
+    let start_index = grid.index(start.x, start.y)?;
+    let end_index = grid.index(end.x, end.y)?;
+
+    g_scores[start_index] = 0.0;
+    parents[start_index] = start_index;
+    open_nodes.push(OpenNode {
+        f: euclidean_distance(start, end),
+        index: start_index,
+    });
+
+    while let Some(OpenNode { index, .. }) = open_nodes.pop() {
+        if closed_nodes[index] {
+            continue;
+        }
+
+        let point = to_point(index);
+
+        if lazy && !grid.line_of_sight(to_point(parents[index]), point) {
+            // The optimistic parent assumed when this node was opened is not
+            // visible, so fall back to the best expanded neighbor.
+            g_scores[index] = f64::INFINITY;
+            for neighbor in get_neighbor_points(grid, point) {
+                let neighbor_index = point_to_index(neighbor, width);
+                if closed_nodes[neighbor_index] && g_scores[neighbor_index] + 1.0 < g_scores[index]
+                {
+                    g_scores[index] = g_scores[neighbor_index] + 1.0;
+                    parents[index] = neighbor_index;
+                }
+            }
+        }
+
+        if index == end_index {
+            return Some(retrace_path(&parents, index, to_point));
+        }
+
+        closed_nodes[index] = true;
+
+        for neighbor in get_neighbor_points(grid, point) {
+            let neighbor_index = point_to_index(neighbor, width);
+            if closed_nodes[neighbor_index] {
+                continue;
+            }
+
+            let parent = parents[index];
+            let parent_point = to_point(parent);
+
+            let (candidate, g) = if lazy || grid.line_of_sight(parent_point, neighbor) {
+                (
+                    parent,
+                    g_scores[parent] + euclidean_distance(parent_point, neighbor),
+                )
+            } else {
+                (index, g_scores[index] + 1.0)
+            };
+
+            if g < g_scores[neighbor_index] {
+                g_scores[neighbor_index] = g;
+                parents[neighbor_index] = candidate;
+                open_nodes.push(OpenNode {
+                    f: g + euclidean_distance(neighbor, end),
+                    index: neighbor_index,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the waypoints from the start to the node at `index`.
+fn retrace_path(
+    parents: &[usize],
+    mut index: usize,
+    to_point: impl Fn(usize) -> Point,
+) -> Vec<Point> {
+    let mut path = vec![to_point(index)];
+
+    while parents[index] != index {
+        index = parents[index];
+        path.push(to_point(index));
+    }
+
+    path.reverse();
+    path
+}
+
+/// Straight-line distance between two points.
+#[inline]
+fn euclidean_distance(a: Point, b: Point) -> f64 {
+    ((a.x - b.x) as f64).hypot((a.y - b.y) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_visible(grid: &Grid, path: &[Point]) {
+        for window in path.windows(2) {
+            assert!(
+                grid.line_of_sight(window[0], window[1]),
+                "No line of sight between {} and {}",
+                window[0],
+                window[1]
+            );
+        }
+    }
+
+    #[test]
+    fn test_open_grid() {
+        let grid = Grid::new(10, 10);
+        let start = Point::new(0, 0);
+        let end = Point::new(9, 6);
+
+        assert_eq!(theta_star(&grid, start, end), Some(vec![start, end]));
+        assert_eq!(lazy_theta_star(&grid, start, end), Some(vec![start, end]));
+    }
+
+    #[test]
+    fn test_around_wall() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, false, false, false, false],
+            vec![false, false, true,  false, false],
+            vec![false, false, true,  false, false],
+            vec![false, false, true,  false, false],
+            vec![false, false, false, false, false],
+        ]);
+
+        let start = Point::new(0, 2);
+        let end = Point::new(4, 2);
+
+        for path in [
+            theta_star(&grid, start, end).unwrap(),
+            lazy_theta_star(&grid, start, end).unwrap(),
+        ] {
+            assert_eq!(path.first(), Some(&start));
+            assert_eq!(path.last(), Some(&end));
+            assert!(path.len() < 5, "Path {path:?} was not shortened");
+            assert_visible(&grid, &path);
+        }
+    }
+
+    #[test]
+    fn test_no_path() {
+        #[rustfmt::skip]
+        let grid = Grid::from_2d(vec![
+            vec![false, true,  false],
+            vec![false, true,  false],
+        ]);
+
+        assert!(theta_star(&grid, Point::new(0, 0), Point::new(2, 1)).is_none());
+        assert!(lazy_theta_star(&grid, Point::new(0, 0), Point::new(2, 1)).is_none());
+    }
+}